@@ -0,0 +1,73 @@
+//! Times the helpers the solvers spend most of their time in.
+//!
+//! Run with `cargo bench --features bench --bench helpers`.
+
+use advent_of_code_2025::solutions::*;
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use std::hint::black_box;
+
+fn day2_generate_invalid_id(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day2::generate_invalid_id");
+    for (num, repetitions) in [(12, 2), (12345, 2), (123, 5), (9, 10)] {
+        group.bench_function(format!("{num}x{repetitions}"), |b| {
+            b.iter(|| day2::bench::generate_invalid_id(black_box(num), black_box(repetitions)))
+        });
+    }
+    group.finish();
+}
+
+fn day3_select_max(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day3::select_max");
+    let input = day3::bench::input(1);
+    for batteries in [2, 12, 19, 50] {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(batteries),
+            &batteries,
+            |b, n| b.iter(|| day3::bench::select_max(black_box(&input), *n)),
+        );
+    }
+    group.finish();
+}
+
+fn day5_merge_ranges(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day5::merge_ranges");
+    for size in [100, 1_000, 10_000] {
+        let parsed = day5::bench::parse(&day5::bench::input(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &parsed, |b, parsed| {
+            b.iter(|| day5::bench::merge_ranges(parsed))
+        });
+    }
+    group.finish();
+}
+
+fn day8_get_distances(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day8::get_distances");
+    for size in [100, 300, 1_000] {
+        let parsed = day8::bench::parse(&day8::bench::input(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &parsed, |b, parsed| {
+            b.iter(|| day8::bench::get_distances(parsed))
+        });
+    }
+    group.finish();
+}
+
+fn day10_fewest_presses(c: &mut Criterion) {
+    let mut group = c.benchmark_group("day10::fewest_presses");
+    for size in [10, 50, 200] {
+        let parsed = day10::bench::parse(&day10::bench::input(size));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &parsed, |b, parsed| {
+            b.iter(|| day10::bench::fewest_presses(parsed))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    day2_generate_invalid_id,
+    day3_select_max,
+    day5_merge_ranges,
+    day8_get_distances,
+    day10_fewest_presses
+);
+criterion_main!(benches);
@@ -0,0 +1,49 @@
+//! Times each day's parse and parts on generated inputs of a few sizes.
+//!
+//! Run with `cargo bench --features bench --bench solvers`, optionally
+//! followed by `-- day7` to pick one day.
+
+use advent_of_code_2025::solutions::*;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+
+/// Benchmarks `parse` and the listed parts of a day's `bench` module for each
+/// input size.
+macro_rules! bench_day {
+    ($c:expr, $day:ident, [$($size:expr),*], [$($part:ident),*]) => {{
+        let mut group = $c.benchmark_group(stringify!($day));
+        for size in [$($size),*] {
+            let input = $day::bench::input(size);
+            let parsed = $day::bench::parse(&input);
+            group.throughput(Throughput::Bytes(input.len() as u64));
+            group.bench_with_input(BenchmarkId::new("parse", size), &input, |b, input| {
+                b.iter(|| $day::bench::parse(input))
+            });
+            $(
+                group.bench_with_input(
+                    BenchmarkId::new(stringify!($part), size),
+                    &parsed,
+                    |b, parsed| b.iter(|| $day::bench::$part(parsed)),
+                );
+            )*
+        }
+        group.finish();
+    }};
+}
+
+fn solvers(c: &mut Criterion) {
+    bench_day!(c, day1, [100, 1_000, 10_000], [part1, part2]);
+    bench_day!(c, day2, [10, 30, 100], [part1, part2, part2_by_listing]);
+    bench_day!(c, day3, [10, 100, 1_000], [part1, part2]);
+    bench_day!(c, day4, [20, 140, 1_000], [part1, part2]);
+    bench_day!(c, day5, [100, 1_000, 10_000], [part1, part2]);
+    bench_day!(c, day6, [100, 1_000, 10_000], [part1, part2]);
+    bench_day!(c, day7, [21, 71, 141], [part1, part2]);
+    bench_day!(c, day8, [100, 300, 1_000], [part1, part2]);
+    bench_day!(c, day9, [25, 50, 100], [part1, part2]);
+    bench_day!(c, day10, [10, 50, 200], [part1, part2]);
+    bench_day!(c, day11, [50, 200, 500], [part1, part2]);
+    bench_day!(c, day12, [10, 100, 1_000], [part1]);
+}
+
+criterion_group!(benches, solvers);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+//! A solver plugin for day 1 that turns the dial one click at a time.
+//!
+//! Build it with `cargo build --example plugin`, then run it with
+//! `cargo run -- --day 1 --plugin target/debug/examples/libplugin.so --solver clicks`.
+//!
+//! It only uses `std`, to show the ABI described in `src/plugin.rs`.
+
+use std::ffi::c_void;
+use std::slice;
+
+type WriteFn = unsafe extern "C" fn(ctx: *mut c_void, part: u32, text: *const u8, len: usize);
+type SolveFn =
+    unsafe extern "C" fn(input: *const u8, len: usize, write: WriteFn, ctx: *mut c_void) -> i32;
+type RegisterFn = unsafe extern "C" fn(
+    ctx: *mut c_void,
+    day: u32,
+    name: *const u8,
+    name_len: usize,
+    solve: SolveFn,
+);
+
+const ABI_VERSION: u32 = 1;
+
+/// # Safety
+///
+/// `register` and `ctx` must be the ones the host passed in.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn aoc_register(register: RegisterFn, ctx: *mut c_void) -> u32 {
+    let name = "clicks";
+    unsafe { register(ctx, 1, name.as_ptr(), name.len(), solve) };
+    ABI_VERSION
+}
+
+unsafe extern "C" fn solve(input: *const u8, len: usize, write: WriteFn, ctx: *mut c_void) -> i32 {
+    let input = match len {
+        0 => &[],
+        len => unsafe { slice::from_raw_parts(input, len) },
+    };
+    let answers = std::str::from_utf8(input)
+        .map_err(|_| "input is not UTF-8".to_string())
+        .and_then(clicks);
+
+    match answers {
+        Ok((landed, passed)) => {
+            for (part, answer) in [(1, landed), (2, passed)] {
+                let answer = answer.to_string();
+                unsafe { write(ctx, part, answer.as_ptr(), answer.len()) };
+            }
+            0
+        }
+        Err(message) => {
+            unsafe { write(ctx, 0, message.as_ptr(), message.len()) };
+            1
+        }
+    }
+}
+
+/// How often the dial ends a rotation at zero, and how often it points at
+/// zero at all.
+fn clicks(input: &str) -> Result<(u64, u64), String> {
+    let (mut position, mut landed, mut passed) = (50, 0, 0);
+    for line in input.lines() {
+        let (step, distance) = match line.split_at_checked(1) {
+            Some(("L", distance)) => (99, distance),
+            Some(("R", distance)) => (1, distance),
+            _ => return Err(format!("expected a rotation, found `{line}`")),
+        };
+        let distance: u64 = distance
+            .parse()
+            .map_err(|_| format!("expected a distance, found `{line}`"))?;
+        for _ in 0..distance {
+            position = (position + step) % 100;
+            passed += u64::from(position == 0);
+        }
+        landed += u64::from(position == 0);
+    }
+    Ok((landed, passed))
+}
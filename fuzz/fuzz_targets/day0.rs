@@ -0,0 +1,11 @@
+#![no_main]
+
+// Day 0's solver only splits its input into lines, so it is its parser.
+use advent_of_code_2025::solutions::{day0::Day0Solver, solution::Solver};
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = Day0Solver.solve(input);
+    }
+});
@@ -0,0 +1,10 @@
+#![no_main]
+
+use advent_of_code_2025::solutions::day2;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(input) = std::str::from_utf8(data) {
+        let _ = day2::bench::try_parse(input);
+    }
+});
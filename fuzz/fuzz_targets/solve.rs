@@ -0,0 +1,21 @@
+#![no_main]
+
+// Runs a whole solver, for the day in the first byte, on the rest. Inputs are
+// kept small so slow searches like day 9's and day 10's don't turn every run
+// into a timeout.
+use advent_of_code_2025::solutions;
+use libfuzzer_sys::fuzz_target;
+
+const MAX_INPUT: usize = 512;
+
+fuzz_target!(|data: &[u8]| {
+    let Some((day, input)) = data.split_first() else {
+        return;
+    };
+    if input.len() > MAX_INPUT {
+        return;
+    }
+    if let (Some(solver), Ok(input)) = (solutions::solver(day % 13), std::str::from_utf8(input)) {
+        let _ = solver.solve(input);
+    }
+});
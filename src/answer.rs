@@ -0,0 +1,154 @@
+use std::fmt;
+
+#[cfg(feature = "bigint")]
+type Repr = num_bigint::BigUint;
+#[cfg(not(feature = "bigint"))]
+type Repr = u64;
+
+/// A non-negative count for answers that can grow past `u64`.
+///
+/// Without the `bigint` feature this is a `u64` whose arithmetic reports
+/// `Overflow` instead of wrapping; with it, counts are arbitrary precision
+/// and never overflow.
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Count(Repr);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Overflow;
+
+impl fmt::Display for Overflow {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "answer overflows u64, rebuild with `--features bigint`")
+    }
+}
+
+impl std::error::Error for Overflow {}
+
+impl Count {
+    pub fn zero() -> Self {
+        Self::from(0)
+    }
+
+    pub fn one() -> Self {
+        Self::from(1)
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, Overflow> {
+        self.0.checked_add(rhs.0).map(Self).ok_or(Overflow)
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self, Overflow> {
+        self.0.checked_mul(rhs.0).map(Self).ok_or(Overflow)
+    }
+
+    #[cfg(not(feature = "bigint"))]
+    pub fn checked_pow(&self, exp: u32) -> Result<Self, Overflow> {
+        self.0.checked_pow(exp).map(Self).ok_or(Overflow)
+    }
+
+    /// Fails with `Overflow` if `rhs` is larger, as counts can't be negative.
+    #[cfg(not(feature = "bigint"))]
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self, Overflow> {
+        self.0.checked_sub(rhs.0).map(Self).ok_or(Overflow)
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn checked_add(&self, rhs: &Self) -> Result<Self, Overflow> {
+        Ok(Self(&self.0 + &rhs.0))
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn checked_mul(&self, rhs: &Self) -> Result<Self, Overflow> {
+        Ok(Self(&self.0 * &rhs.0))
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn checked_pow(&self, exp: u32) -> Result<Self, Overflow> {
+        Ok(Self(self.0.pow(exp)))
+    }
+
+    #[cfg(feature = "bigint")]
+    pub fn checked_sub(&self, rhs: &Self) -> Result<Self, Overflow> {
+        match self.0 >= rhs.0 {
+            true => Ok(Self(&self.0 - &rhs.0)),
+            false => Err(Overflow),
+        }
+    }
+
+    pub fn sum<'a>(counts: impl IntoIterator<Item = &'a Self>) -> Result<Self, Overflow> {
+        counts
+            .into_iter()
+            .try_fold(Self::zero(), |acc, c| acc.checked_add(c))
+    }
+
+    pub fn product<'a>(counts: impl IntoIterator<Item = &'a Self>) -> Result<Self, Overflow> {
+        counts
+            .into_iter()
+            .try_fold(Self::one(), |acc, c| acc.checked_mul(c))
+    }
+}
+
+impl From<u64> for Count {
+    fn from(value: u64) -> Self {
+        Self(Repr::from(value))
+    }
+}
+
+impl TryFrom<u128> for Count {
+    type Error = Overflow;
+
+    #[cfg(not(feature = "bigint"))]
+    fn try_from(value: u128) -> Result<Self, Overflow> {
+        u64::try_from(value).map(Self).map_err(|_| Overflow)
+    }
+
+    #[cfg(feature = "bigint")]
+    fn try_from(value: u128) -> Result<Self, Overflow> {
+        Ok(Self(Repr::from(value)))
+    }
+}
+
+impl fmt::Display for Count {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_arithmetic() {
+        let (a, b) = (Count::from(6), Count::from(7));
+        assert_eq!(a.checked_add(&b), Ok(Count::from(13)));
+        assert_eq!(Count::product([&a, &b, &b]), Ok(Count::from(294)));
+        assert_eq!(Count::sum([]), Ok(Count::zero()));
+        assert_eq!(Count::from(10).checked_pow(3), Ok(Count::from(1000)));
+        assert_eq!(Count::try_from(42u128), Ok(Count::from(42)));
+        assert_eq!(b.checked_sub(&a), Ok(Count::one()));
+        assert_eq!(a.checked_sub(&b), Err(Overflow));
+    }
+
+    #[test]
+    #[cfg(not(feature = "bigint"))]
+    fn test_overflow() {
+        let max = Count::from(u64::MAX);
+        assert_eq!(max.checked_add(&Count::one()), Err(Overflow));
+        assert_eq!(max.checked_mul(&Count::from(2)), Err(Overflow));
+        assert_eq!(Count::from(10).checked_pow(20), Err(Overflow));
+        assert_eq!(Count::try_from(1u128 << 64), Err(Overflow));
+    }
+
+    #[test]
+    #[cfg(feature = "bigint")]
+    fn test_overflow() {
+        let max = Count::from(u64::MAX);
+        let doubled = max.checked_mul(&Count::from(2)).unwrap();
+        assert_eq!(doubled.to_string(), "36893488147419103230");
+        let wide = Count::try_from(1u128 << 64).unwrap();
+        assert_eq!(wide.to_string(), "18446744073709551616");
+    }
+}
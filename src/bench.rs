@@ -0,0 +1,62 @@
+//! Support for the criterion benchmarks in `benches/`.
+//!
+//! Each day has a `bench` module, behind the `bench` feature, exposing the
+//! same shape: `input(size)` generates a puzzle input, `parse` reads it, and
+//! `part1`/`part2` solve the parsed input. Hot helpers are wrapped there too.
+
+/// A small xorshift generator, so generated inputs are the same on every run.
+pub struct Rng(u64);
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        // Xorshift stays at zero once there.
+        Self(seed.max(1))
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        let mut x = self.0;
+        x ^= x << 13;
+        x ^= x >> 7;
+        x ^= x << 17;
+        self.0 = x;
+        x
+    }
+
+    /// A number in `low..high`.
+    pub fn range(&mut self, low: u64, high: u64) -> u64 {
+        low + self.next_u64() % (high - low)
+    }
+
+    /// True with probability `percent` in 100.
+    pub fn chance(&mut self, percent: u64) -> bool {
+        self.range(0, 100) < percent
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::solutions;
+
+    #[test]
+    fn test_generated_inputs_solve() {
+        let inputs = [
+            (1, solutions::day1::bench::input(100)),
+            (2, solutions::day2::bench::input(10)),
+            (3, solutions::day3::bench::input(10)),
+            (4, solutions::day4::bench::input(20)),
+            (5, solutions::day5::bench::input(50)),
+            (6, solutions::day6::bench::input(20)),
+            (7, solutions::day7::bench::input(21)),
+            (8, solutions::day8::bench::input(100)),
+            (9, solutions::day9::bench::input(10)),
+            (11, solutions::day11::bench::input(50)),
+            (12, solutions::day12::bench::input(10)),
+        ];
+        for (day, input) in inputs {
+            let solver = solutions::solver(day).unwrap();
+            if let Err(e) = solver.solve(&input) {
+                panic!("day {day} failed on generated input: {e}\n{input}");
+            }
+        }
+    }
+}
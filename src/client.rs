@@ -0,0 +1,541 @@
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use std::{env, fmt, fs, io, thread};
+
+/// The puzzle site, overridable with `AOC_BASE_URL` for testing.
+pub const BASE_URL: &str = "https://adventofcode.com";
+pub const YEAR: u16 = 2025;
+
+/// Environment variable holding the session cookie.
+pub const SESSION_VAR: &str = "AOC_SESSION";
+/// File holding the session cookie when the variable is unset.
+pub const SESSION_FILE: &str = ".aoc-session";
+
+#[derive(Debug)]
+pub enum Error {
+    NoSession,
+    Http(String),
+    Status(u16),
+    Io(io::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::NoSession => write!(
+                f,
+                "no session token, set {SESSION_VAR} or write it to {SESSION_FILE}"
+            ),
+            Error::Http(e) => write!(f, "request failed: {e}"),
+            Error::Status(status) => write!(f, "server answered with status {status}"),
+            Error::Io(e) => e.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<io::Error> for Error {
+    fn from(value: io::Error) -> Self {
+        Error::Io(value)
+    }
+}
+
+impl From<ureq::Error> for Error {
+    fn from(value: ureq::Error) -> Self {
+        Error::Http(value.to_string())
+    }
+}
+
+/// Where requests go, so the client can be pointed at a stub in tests.
+pub trait Backend {
+    fn get(&self, path: &str) -> Result<String, Error>;
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<String, Error>;
+}
+
+/// Talks HTTP to the puzzle site, or anything else at `base_url`.
+pub struct HttpBackend {
+    base_url: String,
+    session: String,
+    agent: ureq::Agent,
+}
+
+impl HttpBackend {
+    pub fn new(base_url: impl Into<String>, session: impl Into<String>) -> Self {
+        Self {
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            session: session.into(),
+            agent: ureq::Agent::config_builder()
+                .http_status_as_error(false)
+                .build()
+                .into(),
+        }
+    }
+
+    /// Reads the base URL and session token from the environment, falling
+    /// back to the session file.
+    pub fn from_env() -> Result<Self, Error> {
+        let base_url = env::var("AOC_BASE_URL").unwrap_or_else(|_| BASE_URL.to_string());
+        let session = match env::var(SESSION_VAR) {
+            Ok(session) => session,
+            Err(_) => fs::read_to_string(SESSION_FILE).map_err(|_| Error::NoSession)?,
+        };
+        match session.trim() {
+            "" => Err(Error::NoSession),
+            session => Ok(Self::new(base_url, session)),
+        }
+    }
+
+    fn cookie(&self) -> String {
+        format!("session={}", self.session)
+    }
+
+    fn read(response: ureq::http::Response<ureq::Body>) -> Result<String, Error> {
+        let status = response.status().as_u16();
+        if status != 200 {
+            return Err(Error::Status(status));
+        }
+        Ok(response.into_body().read_to_string()?)
+    }
+}
+
+impl Backend for HttpBackend {
+    fn get(&self, path: &str) -> Result<String, Error> {
+        let response = self
+            .agent
+            .get(format!("{}{path}", self.base_url))
+            .header("Cookie", self.cookie())
+            .call()?;
+        Self::read(response)
+    }
+
+    fn post(&self, path: &str, form: &[(&str, &str)]) -> Result<String, Error> {
+        let response = self
+            .agent
+            .post(format!("{}{path}", self.base_url))
+            .header("Cookie", self.cookie())
+            .send_form(form.iter().copied())?;
+        Self::read(response)
+    }
+}
+
+/// What the site made of a submitted answer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Verdict {
+    Correct,
+    Wrong,
+    /// Submitted too soon after the last answer, with the wait if given.
+    TooRecent(Option<Duration>),
+    /// The part is locked or already solved.
+    WrongLevel,
+    /// This answer was already submitted and was wrong; nothing was sent.
+    KnownWrong,
+    Unknown(String),
+}
+
+impl Verdict {
+    fn from_page(page: &str) -> Self {
+        if page.contains("That's the right answer") {
+            Verdict::Correct
+        } else if page.contains("That's not the right answer") {
+            Verdict::Wrong
+        } else if page.contains("You gave an answer too recently") {
+            Verdict::TooRecent(wait(page))
+        } else if page.contains("You don't seem to be solving the right level") {
+            Verdict::WrongLevel
+        } else {
+            Verdict::Unknown(page.to_string())
+        }
+    }
+
+    fn as_str(&self) -> &str {
+        match self {
+            Verdict::Correct => "correct",
+            Verdict::Wrong | Verdict::KnownWrong => "wrong",
+            Verdict::TooRecent(_) => "too recent",
+            Verdict::WrongLevel => "wrong level",
+            Verdict::Unknown(_) => "unknown",
+        }
+    }
+}
+
+impl fmt::Display for Verdict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Verdict::TooRecent(Some(wait)) => write!(f, "too recent, wait {wait:.0?}"),
+            Verdict::KnownWrong => write!(f, "wrong (already submitted)"),
+            verdict => write!(f, "{}", verdict.as_str()),
+        }
+    }
+}
+
+/// Reads the wait out of "You have 1m 5s left to wait".
+fn wait(page: &str) -> Option<Duration> {
+    let (before, _) = page.split_once(" left to wait")?;
+    let text = before.rsplit_once("You have ")?.1;
+    let mut seconds = 0;
+    for part in text.split_whitespace() {
+        seconds += match part.split_at(part.len().checked_sub(1)?) {
+            (n, "m") => n.parse::<u64>().ok()? * 60,
+            (n, "s") => n.parse::<u64>().ok()?,
+            _ => return None,
+        };
+    }
+    Some(Duration::from_secs(seconds))
+}
+
+/// Fetches inputs into a cache directory and submits answers, pausing between
+/// requests and never resending an answer already known to be wrong.
+///
+/// When the next request may go is saved in the cache directory, so the
+/// pauses hold across clients and runs.
+pub struct Client<B: Backend> {
+    backend: B,
+    inputs: PathBuf,
+    interval: Duration,
+}
+
+impl<B: Backend> Client<B> {
+    pub fn new(backend: B, inputs: impl Into<PathBuf>) -> Self {
+        Self {
+            backend,
+            inputs: inputs.into(),
+            interval: Duration::from_secs(5),
+        }
+    }
+
+    /// The minimum time between two requests.
+    pub fn with_interval(mut self, interval: Duration) -> Self {
+        self.interval = interval;
+        self
+    }
+
+    pub fn inputs(&self) -> &Path {
+        &self.inputs
+    }
+
+    /// Where the input for `day` is cached.
+    pub fn input_path(&self, day: u8) -> PathBuf {
+        self.inputs.join(format!("day{day}"))
+    }
+
+    fn history_path(&self, day: u8) -> PathBuf {
+        history_path(&self.inputs, day)
+    }
+
+    fn next_request_path(&self) -> PathBuf {
+        self.inputs.join(".next-request")
+    }
+
+    /// The input for `day`, downloaded only if it isn't cached yet.
+    pub fn input(&self, day: u8) -> Result<String, Error> {
+        let path = self.input_path(day);
+        if let Ok(input) = fs::read_to_string(&path) {
+            return Ok(input);
+        }
+        self.throttle(self.interval)?;
+        let input = self.backend.get(&format!("/{YEAR}/day/{day}/input"))?;
+        fs::create_dir_all(&self.inputs)?;
+        fs::write(&path, &input)?;
+        Ok(input)
+    }
+
+    /// Submits `answer` for `part` of `day`, unless it was already rejected.
+    pub fn submit(&self, day: u8, part: u8, answer: &str) -> Result<Verdict, Error> {
+        let history = self.history(day)?;
+        if history
+            .iter()
+            .any(|(p, a, verdict)| *p == part && a == answer && verdict == "wrong")
+        {
+            return Ok(Verdict::KnownWrong);
+        }
+
+        self.throttle(self.interval)?;
+        let level = part.to_string();
+        let page = self.backend.post(
+            &format!("/{YEAR}/day/{day}/answer"),
+            &[("level", &level), ("answer", answer)],
+        )?;
+        let verdict = Verdict::from_page(&page);
+
+        match &verdict {
+            Verdict::TooRecent(wait) => self.hold(wait.unwrap_or(self.interval))?,
+            Verdict::Correct | Verdict::Wrong => {
+                self.record(day, part, answer, &verdict)?;
+            }
+            _ => (),
+        }
+        Ok(verdict)
+    }
+
+    /// Every recorded `(part, answer, verdict)` for `day`.
+    pub fn history(&self, day: u8) -> Result<Vec<(u8, String, String)>, Error> {
+        history(&self.inputs, day)
+    }
+
+    fn record(&self, day: u8, part: u8, answer: &str, verdict: &Verdict) -> io::Result<()> {
+        use io::Write;
+        fs::create_dir_all(&self.inputs)?;
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.history_path(day))?;
+        writeln!(file, "{part}\t{}\t{}", escape(answer), verdict.as_str())
+    }
+
+    /// Sleeps until the saved wait is over, then starts a new one.
+    fn throttle(&self, wait: Duration) -> io::Result<()> {
+        if let Some(next) = self.next_request() {
+            thread::sleep(next.duration_since(SystemTime::now()).unwrap_or_default());
+        }
+        self.hold(wait)
+    }
+
+    /// Saves that no request may go until `wait` from now.
+    fn hold(&self, wait: Duration) -> io::Result<()> {
+        let next = SystemTime::now() + wait;
+        let millis = next
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        fs::create_dir_all(&self.inputs)?;
+        fs::write(self.next_request_path(), millis.to_string())
+    }
+
+    /// When the next request may go, if a wait was saved.
+    fn next_request(&self) -> Option<SystemTime> {
+        let millis = fs::read_to_string(self.next_request_path()).ok()?;
+        Some(UNIX_EPOCH + Duration::from_millis(millis.trim().parse().ok()?))
+    }
+}
+
+fn history_path(inputs: &Path, day: u8) -> PathBuf {
+    inputs.join(format!("day{day}.answers"))
+}
+
+/// Every `(part, answer, verdict)` recorded for `day` under `inputs`, which
+/// needs no session to read.
+pub fn history(inputs: &Path, day: u8) -> Result<Vec<(u8, String, String)>, Error> {
+    let text = match fs::read_to_string(history_path(inputs, day)) {
+        Ok(text) => text,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(vec![]),
+        Err(e) => return Err(e.into()),
+    };
+    Ok(text
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.splitn(3, '\t');
+            let part = fields.next()?.parse().ok()?;
+            Some((part, unescape(fields.next()?), unescape(fields.next()?)))
+        })
+        .collect())
+}
+
+/// Backslash-escapes the characters that would break up a history line.
+fn escape(field: &str) -> String {
+    let mut escaped = String::with_capacity(field.len());
+    for c in field.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Undoes [`escape`]. A backslash before anything else is kept as it is.
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut chars = field.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            unescaped.push(c);
+            continue;
+        }
+        match chars.next() {
+            Some('t') => unescaped.push('\t'),
+            Some('n') => unescaped.push('\n'),
+            Some('r') => unescaped.push('\r'),
+            Some('\\') => unescaped.push('\\'),
+            Some(c) => unescaped.extend(['\\', c]),
+            None => unescaped.push('\\'),
+        }
+    }
+    unescaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+    use tiny_http::{Response, Server};
+
+    /// Serves canned pages on localhost, reporting each request it sees.
+    fn stub(pages: Vec<&'static str>) -> (String, mpsc::Receiver<String>) {
+        let server = Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for page in pages {
+                let mut request = server.recv().unwrap();
+                let mut body = String::new();
+                request.as_reader().read_to_string(&mut body).unwrap();
+                let cookie = request
+                    .headers()
+                    .iter()
+                    .find(|h| h.field.equiv("Cookie"))
+                    .map(|h| h.value.to_string())
+                    .unwrap_or_default();
+                sender
+                    .send(format!(
+                        "{} {} {cookie} {body}",
+                        request.method(),
+                        request.url()
+                    ))
+                    .unwrap();
+                request.respond(Response::from_string(page)).unwrap();
+            }
+        });
+        (format!("http://127.0.0.1:{port}"), receiver)
+    }
+
+    fn client(base_url: &str, name: &str) -> Client<HttpBackend> {
+        let dir = env::temp_dir().join(format!("aoc-client-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        Client::new(HttpBackend::new(base_url, "abc"), dir).with_interval(Duration::ZERO)
+    }
+
+    #[test]
+    fn test_input_is_cached() {
+        let (url, requests) = stub(vec!["1\n2\n"]);
+        let client = client(&url, "input");
+
+        assert_eq!(client.input(3).unwrap(), "1\n2\n");
+        assert_eq!(
+            requests.recv().unwrap(),
+            "GET /2025/day/3/input session=abc "
+        );
+        assert_eq!(client.input(3).unwrap(), "1\n2\n");
+        assert!(requests.try_recv().is_err());
+        fs::remove_dir_all(client.inputs()).unwrap();
+    }
+
+    #[test]
+    fn test_submit() {
+        let (url, requests) = stub(vec![
+            "<p>That's not the right answer.</p>",
+            "<p>You gave an answer too recently. You have 1m 5s left to wait.</p>",
+            "<p>That's the right answer!</p>",
+        ]);
+        let client = client(&url, "submit");
+
+        assert_eq!(client.submit(1, 1, "42").unwrap(), Verdict::Wrong);
+        assert_eq!(
+            requests.recv().unwrap(),
+            "POST /2025/day/1/answer session=abc level=1&answer=42"
+        );
+        assert_eq!(client.submit(1, 1, "42").unwrap(), Verdict::KnownWrong);
+        assert!(requests.try_recv().is_err());
+
+        assert_eq!(
+            client.submit(1, 1, "43").unwrap(),
+            Verdict::TooRecent(Some(Duration::from_secs(65)))
+        );
+        fs::remove_file(client.next_request_path()).unwrap();
+        assert_eq!(client.submit(1, 1, "43").unwrap(), Verdict::Correct);
+        assert_eq!(
+            client.history(1).unwrap(),
+            vec![
+                (1, "42".to_string(), "wrong".to_string()),
+                (1, "43".to_string(), "correct".to_string()),
+            ]
+        );
+        fs::remove_dir_all(client.inputs()).unwrap();
+    }
+
+    #[test]
+    fn test_throttle_across_clients() {
+        let (url, requests) = stub(vec![
+            "1\n",
+            "<p>You gave an answer too recently. You have 1m 5s left to wait.</p>",
+        ]);
+        let interval = Duration::from_millis(300);
+        let first = client(&url, "throttle").with_interval(interval);
+        let inputs = first.inputs().to_path_buf();
+        first.input(1).unwrap();
+        requests.recv().unwrap();
+
+        // A new client, as in the next run, still waits out the interval.
+        let second = Client::new(HttpBackend::new(&url, "abc"), &inputs);
+        let start = SystemTime::now();
+        assert_eq!(
+            second.submit(1, 1, "42").unwrap(),
+            Verdict::TooRecent(Some(Duration::from_secs(65)))
+        );
+        assert!(start.elapsed().unwrap() >= interval / 2);
+
+        // And the next one the wait the server asked for.
+        let third = Client::new(HttpBackend::new(&url, "abc"), &inputs);
+        let next = third.next_request().unwrap();
+        assert!(next >= SystemTime::now() + Duration::from_secs(60));
+        fs::remove_dir_all(&inputs).unwrap();
+    }
+
+    /// Answers every post with `page`, as if the saved wait had already run
+    /// out by the time the site replied.
+    struct Expired {
+        next_request: PathBuf,
+        page: &'static str,
+    }
+
+    impl Backend for Expired {
+        fn get(&self, _: &str) -> Result<String, Error> {
+            unreachable!()
+        }
+
+        fn post(&self, _: &str, _: &[(&str, &str)]) -> Result<String, Error> {
+            fs::remove_file(&self.next_request)?;
+            Ok(self.page.to_string())
+        }
+    }
+
+    #[test]
+    fn test_too_recent_without_wait() {
+        let inputs = env::temp_dir().join(format!("aoc-client-no-wait-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&inputs);
+        let backend = Expired {
+            next_request: inputs.join(".next-request"),
+            page: "<p>You gave an answer too recently.</p>",
+        };
+        let client = Client::new(backend, &inputs).with_interval(Duration::from_secs(3600));
+
+        assert_eq!(client.submit(1, 1, "42").unwrap(), Verdict::TooRecent(None));
+        let next = client.next_request().unwrap();
+        assert!(next >= SystemTime::now() + Duration::from_secs(3500));
+        fs::remove_dir_all(&inputs).unwrap();
+    }
+
+    #[test]
+    fn test_history_escapes_answers() {
+        let (url, _requests) = stub(vec!["<p>That's not the right answer.</p>"]);
+        let client = client(&url, "escape");
+        let answer = "a\tb\tc\nd\\t";
+
+        assert_eq!(client.submit(2, 2, answer).unwrap(), Verdict::Wrong);
+        assert_eq!(
+            client.history(2).unwrap(),
+            vec![(2, answer.to_string(), "wrong".to_string())]
+        );
+        assert_eq!(client.submit(2, 2, answer).unwrap(), Verdict::KnownWrong);
+        fs::remove_dir_all(client.inputs()).unwrap();
+    }
+
+    #[test]
+    fn test_connection_error() {
+        let client = client("http://127.0.0.1:1", "connection");
+        assert!(matches!(client.input(1), Err(Error::Http(_))));
+    }
+}
@@ -0,0 +1,271 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fmt::Write;
+
+pub type NodeId = usize;
+
+/// How far a depth-first search has got with a node.
+#[derive(Clone, Copy, PartialEq)]
+enum Mark {
+    New,
+    Active,
+    Done,
+}
+
+/// Directed graph whose nodes are identified by arbitrary string names.
+///
+/// Names are interned into dense `NodeId`s on first use, so lookups after
+/// parsing are plain vector indexing.
+#[derive(Default, Debug, Clone)]
+pub struct Graph {
+    names: Vec<String>,
+    ids: HashMap<String, NodeId>,
+    edges: Vec<Vec<NodeId>>,
+}
+
+impl Graph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the id for `name`, adding a new node if it hasn't been seen.
+    pub fn intern(&mut self, name: &str) -> NodeId {
+        if let Some(id) = self.ids.get(name) {
+            return *id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.edges.push(Vec::new());
+        id
+    }
+
+    pub fn id(&self, name: &str) -> Option<NodeId> {
+        self.ids.get(name).copied()
+    }
+
+    pub fn name(&self, id: NodeId) -> &str {
+        &self.names[id]
+    }
+
+    /// Adds an edge between two named nodes. Repeated edges are ignored.
+    pub fn add_edge(&mut self, from: &str, to: &str) {
+        let (from, to) = (self.intern(from), self.intern(to));
+        if !self.edges[from].contains(&to) {
+            self.edges[from].push(to);
+        }
+    }
+
+    pub fn successors(&self, id: NodeId) -> &[NodeId] {
+        &self.edges[id]
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.names.is_empty()
+    }
+
+    pub fn nodes(&self) -> impl Iterator<Item = NodeId> {
+        0..self.len()
+    }
+
+    /// Kahn's algorithm. Returns `None` if the graph has a cycle.
+    pub fn topological_sort(&self) -> Option<Vec<NodeId>> {
+        let mut in_degree = vec![0; self.len()];
+        for node in self.nodes() {
+            for next in self.successors(node) {
+                in_degree[*next] += 1;
+            }
+        }
+
+        let mut queue: VecDeque<NodeId> = self.nodes().filter(|n| in_degree[*n] == 0).collect();
+        let mut order = Vec::with_capacity(self.len());
+
+        while let Some(node) = queue.pop_front() {
+            order.push(node);
+            for next in self.successors(node) {
+                in_degree[*next] -= 1;
+                if in_degree[*next] == 0 {
+                    queue.push_back(*next);
+                }
+            }
+        }
+
+        (order.len() == self.len()).then_some(order)
+    }
+
+    /// Returns the nodes of some cycle, in edge order, if there is one.
+    pub fn find_cycle(&self) -> Option<Vec<NodeId>> {
+        let mut marks = vec![Mark::New; self.len()];
+        let mut finished = vec![];
+        self.nodes()
+            .find_map(|root| self.depth_first(root, &mut marks, &mut finished).err())
+    }
+
+    /// The nodes reachable from `from`, `from` first and every node before
+    /// its successors, or the nodes of a cycle among them. Nodes that can't
+    /// be reached, and any cycles between them, are left alone.
+    pub fn topological_sort_from(&self, from: NodeId) -> Result<Vec<NodeId>, Vec<NodeId>> {
+        let mut marks = vec![Mark::New; self.len()];
+        let mut finished = vec![];
+        self.depth_first(from, &mut marks, &mut finished)?;
+        finished.reverse();
+        Ok(finished)
+    }
+
+    /// Visits the nodes reachable from `root` that aren't marked yet, pushing
+    /// each onto `finished` once all its successors are, or returns the
+    /// nodes of a cycle, in edge order, on finding one.
+    fn depth_first(
+        &self,
+        root: NodeId,
+        marks: &mut [Mark],
+        finished: &mut Vec<NodeId>,
+    ) -> Result<(), Vec<NodeId>> {
+        if marks[root] != Mark::New {
+            return Ok(());
+        }
+        let mut stack: Vec<(NodeId, usize)> = vec![(root, 0)];
+        marks[root] = Mark::Active;
+
+        while let Some((node, edge)) = stack.last_mut() {
+            let node = *node;
+            match self.edges[node].get(*edge) {
+                None => {
+                    marks[node] = Mark::Done;
+                    finished.push(node);
+                    stack.pop();
+                }
+                Some(&next) => {
+                    *edge += 1;
+                    match marks[next] {
+                        Mark::New => {
+                            marks[next] = Mark::Active;
+                            stack.push((next, 0));
+                        }
+                        Mark::Active => {
+                            let start = stack.iter().position(|(n, _)| *n == next).unwrap();
+                            return Err(stack[start..].iter().map(|(n, _)| *n).collect());
+                        }
+                        Mark::Done => (),
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub fn has_cycle(&self) -> bool {
+        self.find_cycle().is_some()
+    }
+
+    /// All nodes reachable from `from`, including `from` itself.
+    pub fn reachable(&self, from: NodeId) -> HashSet<NodeId> {
+        let mut seen = HashSet::from([from]);
+        let mut stack = vec![from];
+
+        while let Some(node) = stack.pop() {
+            for next in self.successors(node) {
+                if seen.insert(*next) {
+                    stack.push(*next);
+                }
+            }
+        }
+        seen
+    }
+
+    /// Renders the graph in Graphviz DOT format.
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n");
+        for node in self.nodes() {
+            let from = escape(self.name(node));
+            if self.successors(node).is_empty() {
+                writeln!(dot, "    \"{from}\";").unwrap();
+            }
+            for next in self.successors(node) {
+                writeln!(dot, "    \"{from}\" -> \"{}\";", escape(self.name(*next))).unwrap();
+            }
+        }
+        dot.push('}');
+        dot
+    }
+}
+
+fn escape(name: &str) -> String {
+    name.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn graph(edges: &[(&str, &str)]) -> Graph {
+        let mut g = Graph::new();
+        edges.iter().for_each(|(from, to)| g.add_edge(from, to));
+        g
+    }
+
+    #[test]
+    fn test_intern() {
+        let mut g = graph(&[("a", "longer_name"), ("a", "longer_name")]);
+        assert_eq!(g.len(), 2);
+        assert_eq!(g.intern("a"), 0);
+        assert_eq!(g.name(1), "longer_name");
+        assert_eq!(g.successors(0), &[1]);
+        assert_eq!(g.id("missing"), None);
+    }
+
+    #[test]
+    fn test_topological_sort() {
+        let g = graph(&[("c", "d"), ("a", "b"), ("b", "c"), ("a", "c")]);
+        let order = g.topological_sort().unwrap();
+        let pos = |name| order.iter().position(|n| *n == g.id(name).unwrap());
+        assert!(pos("a") < pos("b") && pos("b") < pos("c") && pos("c") < pos("d"));
+        assert!(!g.has_cycle());
+    }
+
+    #[test]
+    fn test_cycle() {
+        let g = graph(&[("a", "b"), ("b", "c"), ("c", "b"), ("c", "d")]);
+        assert_eq!(g.topological_sort(), None);
+        let cycle: Vec<&str> = g.find_cycle().unwrap().iter().map(|n| g.name(*n)).collect();
+        assert_eq!(cycle, vec!["b", "c"]);
+    }
+
+    #[test]
+    fn test_topological_sort_from() {
+        let g = graph(&[
+            ("a", "b"),
+            ("b", "d"),
+            ("a", "c"),
+            ("c", "d"),
+            ("x", "y"),
+            ("y", "x"),
+        ]);
+        let names =
+            |nodes: Vec<NodeId>| -> Vec<&str> { nodes.iter().map(|n| g.name(*n)).collect() };
+        let order = names(g.topological_sort_from(g.id("a").unwrap()).unwrap());
+        assert_eq!(order, vec!["a", "c", "b", "d"]);
+        let cycle = names(g.topological_sort_from(g.id("y").unwrap()).unwrap_err());
+        assert_eq!(cycle, vec!["y", "x"]);
+    }
+
+    #[test]
+    fn test_reachable() {
+        let g = graph(&[("a", "b"), ("b", "c"), ("d", "a")]);
+        let reachable = g.reachable(g.id("a").unwrap());
+        assert_eq!(reachable.len(), 3);
+        assert!(!reachable.contains(&g.id("d").unwrap()));
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let g = graph(&[("a", "b\"c")]);
+        assert_eq!(
+            g.to_dot(),
+            "digraph {\n    \"a\" -> \"b\\\"c\";\n    \"b\\\"c\";\n}"
+        );
+    }
+}
@@ -0,0 +1,12 @@
+pub mod answer;
+#[cfg(feature = "bench")]
+#[doc(hidden)]
+pub mod bench;
+pub mod client;
+pub mod graph;
+pub mod parsing;
+pub mod plugin;
+pub mod server;
+pub mod solutions;
+pub mod trace;
+pub mod viz;
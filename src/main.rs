@@ -1,45 +1,519 @@
+use advent_of_code_2025::client::{self, Client, HttpBackend};
+use advent_of_code_2025::server::Server;
+use advent_of_code_2025::trace::{NoTrace, PrintTrace, Trace};
+use advent_of_code_2025::{parsing, plugin, solutions, viz};
 use argh::FromArgs;
-use solutions::solution::Solver;
-use std::fs;
-use std::time::Instant;
-
-mod solutions;
+use solutions::day1::{self, Shortest};
+use solutions::day4;
+use solutions::registry::Registry;
+use solutions::solution::{self, Params, Solution, Solver};
+use std::io::{self, BufReader};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use std::{fs, process};
 
 #[derive(FromArgs)]
 /// Executes the given Advent of Code day
 struct Args {
     /// the day to run
     #[argh(option)]
+    day: Option<u8>,
+
+    /// print the intermediate steps of the solver
+    #[argh(switch)]
+    explain: bool,
+
+    /// write visualisation frames to this directory
+    #[argh(option)]
+    viz: Option<PathBuf>,
+
+    /// a solver setting as `key=value`, may be repeated
+    #[argh(option)]
+    param: Vec<String>,
+
+    /// a shared library of extra solvers to load, may be repeated
+    #[argh(option)]
+    plugin: Vec<PathBuf>,
+
+    /// the solver to run, `builtin` by default, may be repeated
+    #[argh(option)]
+    solver: Vec<String>,
+
+    /// compare the answers with those recorded as correct, or else with the
+    /// builtin solver's
+    #[argh(switch)]
+    check: bool,
+
+    /// time this many runs of each solver
+    #[argh(option)]
+    bench: Option<u32>,
+
+    #[argh(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Serve(Serve),
+    Fetch(Fetch),
+    Submit(Submit),
+    Compare(Compare),
+    Rotations(Rotations),
+    Stream(Stream),
+    Rolls(Rolls),
+}
+
+#[derive(FromArgs)]
+/// Answers puzzles over HTTP at `POST /solve/{day}`
+#[argh(subcommand, name = "serve")]
+struct Serve {
+    /// the port to listen on
+    #[argh(option, default = "8080")]
+    port: u16,
+
+    /// the address to listen on, only this machine by default
+    #[argh(option, default = "String::from(\"127.0.0.1\")")]
+    host: String,
+}
+
+#[derive(FromArgs)]
+/// Downloads a day's input into `inputs/`
+#[argh(subcommand, name = "fetch")]
+struct Fetch {
+    /// the day to fetch
+    #[argh(option)]
+    day: u8,
+}
+
+#[derive(FromArgs)]
+/// Solves a day and submits one part's answer
+#[argh(subcommand, name = "submit")]
+struct Submit {
+    /// the day to submit
+    #[argh(option)]
+    day: u8,
+
+    /// the part to submit, 1 or 2
+    #[argh(option)]
+    part: u8,
+
+    /// submit this instead of the solver's answer
+    #[argh(option)]
+    answer: Option<String>,
+}
+
+#[derive(FromArgs)]
+/// Runs every solver for a day on its input and checks they agree
+#[argh(subcommand, name = "compare")]
+struct Compare {
+    /// the day to compare
+    #[argh(option)]
     day: u8,
 }
 
+#[derive(FromArgs)]
+/// Prints a shortest day 1 input that lands on and passes a target the given
+/// number of times
+#[argh(subcommand, name = "rotations")]
+struct Rotations {
+    /// how many rotations should end on the target
+    #[argh(option)]
+    landings: u64,
+
+    /// how many clicks should leave the dial on the target
+    #[argh(option)]
+    passes: u64,
+
+    /// the number of positions on the dial
+    #[argh(option, default = "100")]
+    size: u64,
+
+    /// the position the dial starts at
+    #[argh(option, default = "50")]
+    start: u64,
+
+    /// the position to count
+    #[argh(option, default = "0")]
+    target: u64,
+
+    /// what to keep to a minimum, `rotations` or `clicks`
+    #[argh(option, default = "Shortest::Rotations")]
+    shortest: Shortest,
+}
+
+#[derive(FromArgs)]
+/// Solves day 1 a line at a time from a file or standard input of any size,
+/// using the dial `--param`s
+#[argh(subcommand, name = "stream")]
+struct Stream {
+    /// the file to read, standard input if omitted
+    #[argh(option)]
+    input: Option<PathBuf>,
+
+    /// print the counts so far after every this many rotations, 0 for never
+    #[argh(option, default = "10_000_000")]
+    every: u64,
+}
+
+#[derive(FromArgs)]
+/// Writes day 4's grid once no more rolls can be removed, and the wave that
+/// removed each roll, using the removal rule `--param`s
+#[argh(subcommand, name = "rolls")]
+struct Rolls {
+    /// the directory to write `final.txt` and `waves.txt` to
+    #[argh(option)]
+    out: PathBuf,
+
+    /// also write the grid after each wave as `wave-N.txt`
+    #[argh(switch)]
+    every_wave: bool,
+}
+
 fn main() {
     let args: Args = argh::from_env();
 
-    let file_path = format!("inputs/day{}", args.day);
-    let file_contents = fs::read_to_string(file_path).unwrap();
-
-    let solver: Box<dyn Solver> = match args.day {
-        0 => Box::new(solutions::day0::Day0Solver {}),
-        1 => Box::new(solutions::day1::Day1Solver {}),
-        2 => Box::new(solutions::day2::Day2Solver {}),
-        3 => Box::new(solutions::day3::Day3Solver {}),
-        4 => Box::new(solutions::day4::Day4Solver {}),
-        5 => Box::new(solutions::day5::Day5Solver {}),
-        6 => Box::new(solutions::day6::Day6Solver {}),
-        7 => Box::new(solutions::day7::Day7Solver {}),
-        8 => Box::new(solutions::day8::Day8Solver {}),
-        9 => Box::new(solutions::day9::Day9Solver {}),
-        10 => Box::new(solutions::day10::Day10Solver {}),
-        11 => Box::new(solutions::day11::Day11Solver {}),
-        12 => Box::new(solutions::day12::Day12Solver {}),
-        _ => panic!("Valid days are 1-12"),
+    let mut params = Params::new();
+    for pair in &args.param {
+        match Params::parse_pair(pair) {
+            Ok((key, value)) => params.set(key, value),
+            Err(e) => {
+                eprintln!("{e}");
+                process::exit(1);
+            }
+        }
+    }
+
+    let mut registry = Registry::new();
+    for path in &args.plugin {
+        for (day, name, solver) in exit_on_error(plugin::load(path)) {
+            exit_on_error(
+                registry
+                    .register(day, name, Box::new(solver))
+                    .map_err(plugin::Error::Register),
+            );
+        }
+    }
+
+    match args.command {
+        Some(Command::Serve(serve)) => {
+            let server = exit_on_error(Server::bind((serve.host.as_str(), serve.port)));
+            println!("Listening on {}:{}", serve.host, server.port());
+            exit_on_error(server.run());
+            return;
+        }
+        Some(Command::Fetch(fetch)) => {
+            let client = client();
+            exit_on_error(client.input(fetch.day));
+            println!("Saved {}", client.input_path(fetch.day).display());
+            return;
+        }
+        Some(Command::Submit(submit)) => {
+            if !matches!(submit.part, 1 | 2) {
+                eprintln!("Valid parts are 1 and 2");
+                process::exit(1);
+            }
+            let client = client();
+            let answer = match submit.answer {
+                Some(answer) => answer,
+                None => {
+                    let input = parsing::normalize(&exit_on_error(client.input(submit.day)));
+                    let solver =
+                        exit_on_error(solutions::solver(submit.day).ok_or("Valid days are 1-12"));
+                    let solution = exit_on_error(solver.solve(&input));
+                    match submit.part {
+                        1 => solution.part1,
+                        _ => solution.part2,
+                    }
+                }
+            };
+            let verdict = exit_on_error(client.submit(submit.day, submit.part, &answer));
+            println!(
+                "Day {} part {}: {answer} is {verdict}",
+                submit.day, submit.part
+            );
+            return;
+        }
+        Some(Command::Compare(compare)) => {
+            let input = read_input(compare.day);
+            compare_solvers(compare.day, &input, &params, &registry);
+            return;
+        }
+        Some(Command::Rotations(r)) => {
+            let rotations = exit_on_error(day1::shortest_rotations(
+                r.size, r.start, r.target, r.landings, r.passes, r.shortest,
+            ));
+            println!("{}", day1::format_rotations(&rotations));
+            return;
+        }
+        Some(Command::Stream(stream)) => {
+            let now = Instant::now();
+            let mut dial = exit_on_error(day1::dial(&params)).without_history();
+            let report = |rotations, dial: &day1::Dial| {
+                eprintln!(
+                    "{rotations} rotations: part 1 {}, part 2 {}",
+                    dial.total_landed(),
+                    dial.total_passed()
+                )
+            };
+            let result = match &stream.input {
+                Some(path) => {
+                    let file = BufReader::new(exit_on_error(fs::File::open(path)));
+                    day1::stream(file, &mut dial, stream.every, report)
+                }
+                None => day1::stream(io::stdin().lock(), &mut dial, stream.every, report),
+            };
+            exit_on_error(result);
+            println!(
+                "Part1: {}, Part2: {}",
+                dial.total_landed(),
+                dial.total_passed()
+            );
+            println!("Elapsed: {:.2?}", now.elapsed());
+            return;
+        }
+        Some(Command::Rolls(rolls)) => {
+            let input = read_input(4);
+            let rule = exit_on_error(day4::rule(&params));
+            let removal = exit_on_error(day4::removal(&input, &rule, rolls.every_wave));
+            exit_on_error(write_removal(&rolls.out, &removal));
+            println!("Wrote the removal to {}", rolls.out.display());
+            return;
+        }
+        None => (),
+    }
+
+    let Some(day) = args.day else {
+        eprintln!("Pass --day or a subcommand, see --help");
+        process::exit(1);
+    };
+
+    let names = match args.solver.is_empty() {
+        true => vec![Registry::BUILTIN.to_string()],
+        false => args.solver.clone(),
+    };
+    let solvers: Vec<(&str, &dyn Solver)> = names
+        .iter()
+        .map(|name| match registry.get(day, name) {
+            Some(solver) => (name.as_str(), solver),
+            None => {
+                eprintln!(
+                    "Day {day} has no solver `{name}`, known solvers: {}",
+                    registry.names(day).join(", ")
+                );
+                process::exit(1);
+            }
+        })
+        .collect();
+
+    let file_contents = read_input(day);
+
+    if args.check {
+        check(day, &file_contents, &params, &registry, &solvers);
+        return;
+    }
+    if let Some(runs) = args.bench {
+        bench(&file_contents, &params, &solvers, runs);
+        return;
+    }
+
+    if let Some(dir) = &args.viz {
+        let frames = match solvers[0].1.visualize(&file_contents, &params) {
+            Ok(frames) => frames,
+            Err(e) => {
+                eprintln!("Day {} failed: {e}", day);
+                process::exit(1);
+            }
+        };
+        if frames.is_empty() {
+            println!("Day {} has no visualisation", day);
+        } else {
+            let written = viz::write_frames(dir, &format!("day{}", day), &frames)
+                .map_err(|e| format!("cannot write frames to {}: {e}", dir.display()));
+            exit_on_error(written);
+            println!("Wrote {} frames to {}", frames.len(), dir.display());
+        }
+    }
+
+    let mut trace: Box<dyn Trace> = match args.explain {
+        true => Box::new(PrintTrace),
+        false => Box::new(NoTrace),
     };
 
-    let now = Instant::now();
-    let solution = solver.solve(&file_contents);
+    for (name, solver) in &solvers {
+        let now = Instant::now();
+        let solution = match solver.solve_with(&file_contents, &params, trace.as_mut()) {
+            Ok(solution) => solution,
+            Err(e) => {
+                eprintln!("Day {} failed: {e}", day);
+                process::exit(1);
+            }
+        };
+
+        let elapsed = now.elapsed();
+        if solvers.len() > 1 {
+            println!("{name}:");
+        }
+        println!("Part1: {}, Part2: {}", solution.part1, solution.part2);
+        println!("Elapsed: {:.2?}", elapsed);
+    }
+}
+
+/// Checks each solver's answers against those `submit` recorded as correct,
+/// or the builtin solver's where none are, exiting with 1 on any mismatch.
+fn check(
+    day: u8,
+    input: &str,
+    params: &Params,
+    registry: &Registry,
+    solvers: &[(&str, &dyn Solver)],
+) {
+    let history = exit_on_error(client::history(Path::new("inputs"), day));
+    let builtin = registry
+        .get(day, Registry::BUILTIN)
+        .and_then(|solver| solver.solve_with(input, params, &mut NoTrace).ok());
+    let expected = |part: u8| {
+        let recorded = history
+            .iter()
+            .find(|(p, _, verdict)| *p == part && verdict == "correct")
+            .map(|(_, answer, _)| (answer.as_str(), "recorded"));
+        recorded.or_else(|| {
+            let solution = builtin.as_ref()?;
+            let answer = if part == 1 {
+                &solution.part1
+            } else {
+                &solution.part2
+            };
+            Some((answer.as_str(), Registry::BUILTIN))
+        })
+    };
+
+    let mut failed = false;
+    for (name, solver) in solvers {
+        let solution = match solver.solve_with(input, params, &mut NoTrace) {
+            Ok(solution) => solution,
+            Err(e) => {
+                println!("{name}: failed: {e}");
+                failed = true;
+                continue;
+            }
+        };
+        for (part, answer) in [(1, &solution.part1), (2, &solution.part2)] {
+            match expected(part) {
+                Some((expected, source)) if expected == answer => {
+                    println!("{name} part {part}: {answer} matches the {source} answer")
+                }
+                Some((expected, source)) => {
+                    println!(
+                        "{name} part {part}: {answer} differs from the {source} answer {expected}"
+                    );
+                    failed = true;
+                }
+                None => println!("{name} part {part}: {answer} has nothing to check against"),
+            }
+        }
+    }
+    if failed {
+        process::exit(1);
+    }
+}
+
+/// Runs each solver `runs` times and prints its fastest, median and slowest run.
+fn bench(input: &str, params: &Params, solvers: &[(&str, &dyn Solver)], runs: u32) {
+    for (name, solver) in solvers {
+        let mut times: Vec<Duration> = (0..runs.max(1))
+            .map(|_| {
+                let now = Instant::now();
+                exit_on_error(solver.solve_with(input, params, &mut NoTrace));
+                now.elapsed()
+            })
+            .collect();
+        times.sort();
+        println!(
+            "{name}: min {:.2?}, median {:.2?}, max {:.2?} over {} runs",
+            times[0],
+            times[times.len() / 2],
+            times[times.len() - 1],
+            times.len()
+        );
+    }
+}
+
+fn read_input(day: u8) -> String {
+    let file_path = format!("inputs/day{}", day);
+    let input = fs::read_to_string(&file_path).map_err(|e| format!("cannot read {file_path}: {e}"));
+    parsing::normalize(&exit_on_error(input))
+}
+
+/// Runs every solver for `day`, prints their answers and timings as a table,
+/// and exits with 1 unless they all agree.
+fn compare_solvers(day: u8, input: &str, params: &Params, registry: &Registry) {
+    let names = registry.names(day);
+    if names.is_empty() {
+        eprintln!("Valid days are 1-12");
+        process::exit(1);
+    }
+
+    let results: Vec<(&str, Result<Solution, solution::Error>, Duration)> = names
+        .into_iter()
+        .map(|name| {
+            let solver = registry.get(day, name).unwrap();
+            let now = Instant::now();
+            let result = solver.solve_with(input, params, &mut NoTrace);
+            (name, result, now.elapsed())
+        })
+        .collect();
+
+    let mut rows = vec![["solver", "part 1", "part 2", "time"].map(String::from)];
+    for (name, result, elapsed) in &results {
+        let (part1, part2) = match result {
+            Ok(solution) => (solution.part1.clone(), solution.part2.clone()),
+            Err(e) => (format!("failed: {e}"), String::new()),
+        };
+        rows.push([name.to_string(), part1, part2, format!("{elapsed:.2?}")]);
+    }
+    let widths: Vec<usize> = (0..4)
+        .map(|col| rows.iter().map(|row| row[col].len()).max().unwrap())
+        .collect();
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:<width$}"))
+            .collect();
+        println!("{}", cells.join("  ").trim_end());
+    }
+
+    let first = &results[0].1;
+    if results
+        .iter()
+        .all(|(_, result, _)| result.is_ok() && result == first)
+    {
+        println!("All {} solvers agree", results.len());
+    } else {
+        println!("The solvers disagree");
+        process::exit(1);
+    }
+}
+
+fn client() -> Client<HttpBackend> {
+    Client::new(exit_on_error(HttpBackend::from_env()), "inputs")
+}
+
+/// Writes day 4's final grid, wave map and any steps to `dir`.
+fn write_removal(dir: &Path, removal: &day4::Removal) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    fs::write(dir.join("final.txt"), &removal.grid)?;
+    fs::write(dir.join("waves.txt"), removal.wave_map())?;
+    for (idx, step) in removal.steps.iter().enumerate() {
+        fs::write(dir.join(format!("wave-{}.txt", idx + 1)), step)?;
+    }
+    Ok(())
+}
 
-    let elapsed = now.elapsed();
-    println!("Part1: {}, Part2: {}", solution.part1, solution.part2);
-    println!("Elapsed: {:.2?}", elapsed);
+fn exit_on_error<T>(result: Result<T, impl std::fmt::Display>) -> T {
+    result.unwrap_or_else(|e| {
+        eprintln!("{e}");
+        process::exit(1);
+    })
 }
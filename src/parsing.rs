@@ -0,0 +1,297 @@
+use std::{error, fmt, str::FromStr};
+
+/// A parse failure pointing at a 1-based line and column of the input.
+///
+/// Columns are byte offsets, which is the same as character offsets for the
+/// ASCII puzzle inputs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseError {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl ParseError {
+    pub fn new(line: usize, column: usize, message: impl Into<String>) -> Self {
+        Self {
+            line,
+            column,
+            message: message.into(),
+        }
+    }
+
+    /// An error positioned just past the last line of `input`, for input that
+    /// ends before something required shows up.
+    pub fn end_of_input(input: &str, message: impl Into<String>) -> Self {
+        Self::new(lines(input).count() + 1, 1, message)
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "line {}, column {}: {}",
+            self.line, self.column, self.message
+        )
+    }
+}
+
+impl error::Error for ParseError {}
+
+pub type Result<T> = std::result::Result<T, ParseError>;
+
+/// A slice of the input that remembers where it came from.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Token<'a> {
+    text: &'a str,
+    line: usize,
+    column: usize,
+}
+
+impl<'a> Token<'a> {
+    pub fn new(text: &'a str, line: usize, column: usize) -> Self {
+        Self { text, line, column }
+    }
+
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    pub fn line(&self) -> usize {
+        self.line
+    }
+
+    pub fn column(&self) -> usize {
+        self.column
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn error(&self, message: impl Into<String>) -> ParseError {
+        ParseError::new(self.line, self.column, message)
+    }
+
+    /// Wraps `part`, which must be a subslice of this token's text.
+    fn sub(&self, part: &'a str) -> Token<'a> {
+        let offset = part.as_ptr() as usize - self.text.as_ptr() as usize;
+        Token::new(part, self.line, self.column + offset)
+    }
+
+    pub fn trim(self) -> Token<'a> {
+        self.sub(self.text.trim())
+    }
+
+    pub fn split(self, separator: &'a str) -> impl Iterator<Item = Token<'a>> {
+        self.text.split(separator).map(move |part| self.sub(part))
+    }
+
+    pub fn split_whitespace(self) -> impl Iterator<Item = Token<'a>> {
+        self.text.split_whitespace().map(move |part| self.sub(part))
+    }
+
+    pub fn split_once(self, separator: &str) -> Result<(Token<'a>, Token<'a>)> {
+        match self.text.split_once(separator) {
+            Some((left, right)) => Ok((self.sub(left), self.sub(right))),
+            None => Err(self.error(format!("expected `{separator}` in `{}`", self.text))),
+        }
+    }
+
+    pub fn strip_prefix(self, prefix: &str) -> Result<Token<'a>> {
+        match self.text.strip_prefix(prefix) {
+            Some(rest) => Ok(self.sub(rest)),
+            None => Err(self.error(format!("expected `{prefix}`"))),
+        }
+    }
+
+    /// The contents of a group such as `(1,3)`, `{3,5}` or `[.##.]`.
+    pub fn delimited(self, open: char, close: char) -> Result<Token<'a>> {
+        let inner = self
+            .text
+            .strip_prefix(open)
+            .and_then(|t| t.strip_suffix(close));
+        match inner {
+            Some(inner) => Ok(self.sub(inner)),
+            None => Err(self.error(format!(
+                "expected `{open}...{close}`, found `{}`",
+                self.text
+            ))),
+        }
+    }
+
+    pub fn parse<T>(self) -> Result<T>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.text
+            .parse()
+            .map_err(|e| self.error(format!("cannot parse `{}`: {e}", self.text)))
+    }
+
+    /// Parses a list such as `1,2,3`.
+    pub fn numbers<T>(self, separator: &'a str) -> Result<Vec<T>>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        self.split(separator).map(Token::parse).collect()
+    }
+
+    /// Parses an inclusive range written as `low-high`.
+    pub fn range<T>(self) -> Result<(T, T)>
+    where
+        T: FromStr,
+        T::Err: fmt::Display,
+    {
+        let (low, high) = self.split_once("-")?;
+        Ok((low.parse()?, high.parse()?))
+    }
+
+    pub fn chars(self) -> impl DoubleEndedIterator<Item = (Token<'a>, char)> {
+        self.text
+            .char_indices()
+            .map(move |(idx, c)| (self.sub(&self.text[idx..idx + c.len_utf8()]), c))
+    }
+}
+
+/// Canonical form of a raw input file: LF line endings, no trailing
+/// whitespace on any line and no trailing blank lines.
+pub fn normalize(input: &str) -> String {
+    input
+        .lines()
+        .map(str::trim_end)
+        .collect::<Vec<&str>>()
+        .join("\n")
+        .trim_end_matches('\n')
+        .to_string()
+}
+
+/// The lines of `input`, accepting both LF and CRLF endings and ignoring
+/// trailing newlines.
+pub fn lines(input: &str) -> impl Iterator<Item = Token<'_>> {
+    input
+        .trim_end_matches(['\r', '\n'])
+        .lines()
+        .enumerate()
+        .map(|(idx, line)| Token::new(line, idx + 1, 1))
+}
+
+/// Groups of lines separated by one or more blank lines.
+pub fn sections(input: &str) -> Vec<Vec<Token<'_>>> {
+    let mut sections = vec![];
+    let mut current = vec![];
+
+    for line in lines(input) {
+        if line.text().trim().is_empty() {
+            if !current.is_empty() {
+                sections.push(current);
+                current = vec![];
+            }
+        } else {
+            current.push(line);
+        }
+    }
+    if !current.is_empty() {
+        sections.push(current);
+    }
+    sections
+}
+
+/// Parses a rectangular-ish grid of characters, one row per line.
+pub fn grid<'a, T>(
+    lines: impl IntoIterator<Item = Token<'a>>,
+    cell: impl Fn(char) -> Option<T>,
+) -> Result<Vec<Vec<T>>> {
+    lines
+        .into_iter()
+        .map(|line| {
+            line.chars()
+                .map(|(token, c)| {
+                    cell(c).ok_or_else(|| token.error(format!("unexpected character `{c}`")))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize("a \r\n  b\t\r\n\r\n\r\n"), "a\n  b");
+        assert_eq!(normalize("a\n\nb\n"), "a\n\nb");
+        assert_eq!(normalize(""), "");
+    }
+
+    #[test]
+    fn test_lines() {
+        let parsed: Vec<Token> = lines("ab\r\n\r\ncd\n\n").collect();
+        assert_eq!(
+            parsed,
+            vec![
+                Token::new("ab", 1, 1),
+                Token::new("", 2, 1),
+                Token::new("cd", 3, 1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sections() {
+        let parsed = sections("a\r\nb\r\n\r\n\r\nc\r\n");
+        let texts: Vec<Vec<&str>> = parsed
+            .iter()
+            .map(|s| s.iter().map(|t| t.text()).collect())
+            .collect();
+        assert_eq!(texts, vec![vec!["a", "b"], vec!["c"]]);
+        assert_eq!(parsed[1][0].line(), 5);
+    }
+
+    #[test]
+    fn test_located_errors() {
+        let line = lines("ok\n12-34,5x-6").nth(1).unwrap();
+        let ranges: Result<Vec<(u64, u64)>> = line.split(",").map(Token::range).collect();
+        let err = ranges.unwrap_err();
+        assert_eq!((err.line, err.column), (2, 7));
+        assert_eq!(
+            err.to_string(),
+            "line 2, column 7: cannot parse `5x`: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_delimited() {
+        let line = Token::new("[.##.] (1,3) {3,5}", 1, 1);
+        let groups: Vec<Token> = line.split_whitespace().collect();
+        assert_eq!(groups[0].delimited('[', ']').unwrap().text(), ".##.");
+        assert_eq!(
+            groups[1]
+                .delimited('(', ')')
+                .unwrap()
+                .numbers::<u32>(",")
+                .unwrap(),
+            vec![1, 3]
+        );
+        assert_eq!(groups[2].delimited('{', '}').unwrap().column(), 15);
+        assert_eq!(groups[2].delimited('(', ')').unwrap_err().column, 14);
+    }
+
+    #[test]
+    fn test_grid() {
+        let cell = |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        };
+        assert_eq!(
+            grid(lines("#.\n.#\n"), cell).unwrap(),
+            vec![vec![true, false], vec![false, true]]
+        );
+        assert_eq!(grid(lines("#.\n.x"), cell).unwrap_err().column, 2);
+    }
+}
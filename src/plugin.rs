@@ -0,0 +1,260 @@
+//! Extra solvers loaded from shared libraries through a C ABI.
+//!
+//! A plugin exports an [`EntryFn`] named `aoc_register`. It calls the
+//! [`RegisterFn`] it is given once per solver, with the day, a UTF-8 name and
+//! a [`SolveFn`], and returns the [`ABI_VERSION`] it was built against.
+//!
+//! A [`SolveFn`] gets the UTF-8 input and reports the answers by calling the
+//! [`WriteFn`] it is given with part 1 and 2, or with part 0 and a message
+//! when it fails. It returns 0 on success. The host copies every buffer it is
+//! handed, so plugins keep ownership of their memory.
+//!
+//! `examples/plugin.rs` is a plugin written against nothing but `std`.
+
+use std::ffi::c_void;
+use std::path::Path;
+use std::sync::Arc;
+use std::{fmt, slice};
+
+use libloading::Library;
+
+use crate::solutions::solution::{self, Params, Solution, Solver};
+use crate::trace::Trace;
+
+pub const ABI_VERSION: u32 = 1;
+
+/// The symbol every plugin exports.
+pub const ENTRY_POINT: &str = "aoc_register";
+
+pub type WriteFn = unsafe extern "C" fn(ctx: *mut c_void, part: u32, text: *const u8, len: usize);
+
+pub type SolveFn =
+    unsafe extern "C" fn(input: *const u8, len: usize, write: WriteFn, ctx: *mut c_void) -> i32;
+
+pub type RegisterFn = unsafe extern "C" fn(
+    ctx: *mut c_void,
+    day: u32,
+    name: *const u8,
+    name_len: usize,
+    solve: SolveFn,
+);
+
+pub type EntryFn = unsafe extern "C" fn(register: RegisterFn, ctx: *mut c_void) -> u32;
+
+#[derive(Debug)]
+pub enum Error {
+    Load(libloading::Error),
+    /// The plugin was built against another version of the ABI.
+    Version(u32),
+    /// The plugin registered a solver with a bad day or name.
+    Register(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Load(e) => write!(f, "cannot load plugin: {e}"),
+            Error::Version(version) => write!(
+                f,
+                "plugin uses ABI version {version}, expected {ABI_VERSION}"
+            ),
+            Error::Register(message) => write!(f, "invalid plugin solver: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<libloading::Error> for Error {
+    fn from(value: libloading::Error) -> Self {
+        Error::Load(value)
+    }
+}
+
+/// A solver living in a plugin, which it keeps loaded.
+pub struct PluginSolver {
+    solve: SolveFn,
+    /// `None` for solvers linked into this binary, as in tests.
+    _library: Option<Arc<Library>>,
+}
+
+/// Where [`write`] puts what a plugin hands back.
+#[derive(Default)]
+struct Output {
+    message: Option<String>,
+    part1: Option<String>,
+    part2: Option<String>,
+}
+
+unsafe extern "C" fn write(ctx: *mut c_void, part: u32, text: *const u8, len: usize) {
+    // SAFETY: `ctx` is the `Output` passed to the `SolveFn` by `solve_with`,
+    // and the plugin promises `text` points to `len` bytes.
+    let (output, text) = unsafe { (&mut *(ctx as *mut Output), bytes(text, len)) };
+    let text = String::from_utf8_lossy(text).into_owned();
+    match part {
+        0 => output.message = Some(text),
+        1 => output.part1 = Some(text),
+        2 => output.part2 = Some(text),
+        _ => (),
+    }
+}
+
+/// # Safety
+///
+/// `data` must point to `len` readable bytes, or `len` must be 0.
+unsafe fn bytes<'a>(data: *const u8, len: usize) -> &'a [u8] {
+    match len {
+        0 => &[],
+        len => unsafe { slice::from_raw_parts(data, len) },
+    }
+}
+
+impl Solver for PluginSolver {
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<Solution, solution::Error> {
+        let mut output = Output::default();
+        let ctx = &mut output as *mut Output as *mut c_void;
+        // SAFETY: the plugin promised a `SolveFn` with this signature.
+        let status = unsafe { (self.solve)(input.as_ptr(), input.len(), write, ctx) };
+
+        if status != 0 {
+            return Err(solution::Error::Plugin(
+                output
+                    .message
+                    .unwrap_or_else(|| format!("returned {status}")),
+            ));
+        }
+        Ok(Solution {
+            part1: output.part1.unwrap_or_default(),
+            part2: output.part2.unwrap_or_default(),
+        })
+    }
+}
+
+/// Loads the plugin at `path`, returning each solver it provides with its day
+/// and name.
+pub fn load(path: &Path) -> Result<Vec<(u8, String, PluginSolver)>, Error> {
+    // SAFETY: loading runs the library's initialisers; plugins are trusted.
+    let library = Arc::new(unsafe { Library::new(path) }?);
+    // SAFETY: plugins export `aoc_register` as an `EntryFn`.
+    let entry = *unsafe { library.get::<EntryFn>(ENTRY_POINT.as_bytes()) }?;
+    solvers(entry, Some(library))
+}
+
+type Registered = Vec<(u32, Vec<u8>, SolveFn)>;
+
+unsafe extern "C" fn register(
+    ctx: *mut c_void,
+    day: u32,
+    name: *const u8,
+    name_len: usize,
+    solve: SolveFn,
+) {
+    // SAFETY: `ctx` is the `Registered` passed to the `EntryFn` by `solvers`,
+    // and the plugin promises `name` points to `name_len` bytes.
+    let (registered, name) = unsafe { (&mut *(ctx as *mut Registered), bytes(name, name_len)) };
+    registered.push((day, name.to_vec(), solve));
+}
+
+/// Runs a plugin's entry point and checks what it registers.
+fn solvers(
+    entry: EntryFn,
+    library: Option<Arc<Library>>,
+) -> Result<Vec<(u8, String, PluginSolver)>, Error> {
+    let mut registered = Registered::new();
+    let ctx = &mut registered as *mut Registered as *mut c_void;
+    // SAFETY: the plugin promised an `EntryFn` with this signature.
+    let version = unsafe { entry(register, ctx) };
+    if version != ABI_VERSION {
+        return Err(Error::Version(version));
+    }
+
+    registered
+        .into_iter()
+        .map(|(day, name, solve)| {
+            let name = String::from_utf8(name)
+                .map_err(|_| Error::Register(format!("day {day} name is not UTF-8")))?;
+            let day = u8::try_from(day)
+                .map_err(|_| Error::Register(format!("`{name}` is for day {day}")))?;
+            let solver = PluginSolver {
+                solve,
+                _library: library.clone(),
+            };
+            Ok((day, name, solver))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Answers with the input's length and line count, or fails on empty input.
+    unsafe extern "C" fn measure(
+        input: *const u8,
+        len: usize,
+        write: WriteFn,
+        ctx: *mut c_void,
+    ) -> i32 {
+        let input = std::str::from_utf8(unsafe { bytes(input, len) }).unwrap();
+        if input.is_empty() {
+            let message = "empty input";
+            unsafe { write(ctx, 0, message.as_ptr(), message.len()) };
+            return 1;
+        }
+        for (part, answer) in [(1, input.len()), (2, input.lines().count())] {
+            let answer = answer.to_string();
+            unsafe { write(ctx, part, answer.as_ptr(), answer.len()) };
+        }
+        0
+    }
+
+    unsafe extern "C" fn entry(register: RegisterFn, ctx: *mut c_void) -> u32 {
+        let name = "measure";
+        unsafe { register(ctx, 1, name.as_ptr(), name.len(), measure) };
+        ABI_VERSION
+    }
+
+    unsafe extern "C" fn bad_day(register: RegisterFn, ctx: *mut c_void) -> u32 {
+        unsafe { register(ctx, 300, "far".as_ptr(), 3, measure) };
+        ABI_VERSION
+    }
+
+    unsafe extern "C" fn old_version(_register: RegisterFn, _ctx: *mut c_void) -> u32 {
+        0
+    }
+
+    #[test]
+    fn test_solve() {
+        let mut solvers = solvers(entry, None).unwrap();
+        let (day, name, solver) = solvers.pop().unwrap();
+        assert_eq!((day, name.as_str()), (1, "measure"));
+
+        let solution = solver.solve("L68\nR48").unwrap();
+        assert_eq!(
+            (solution.part1.as_str(), solution.part2.as_str()),
+            ("7", "2")
+        );
+        assert_eq!(
+            solver.solve("").unwrap_err().to_string(),
+            "plugin failed: empty input"
+        );
+    }
+
+    #[test]
+    fn test_invalid_plugins() {
+        assert_eq!(
+            solvers(bad_day, None).err().unwrap().to_string(),
+            "invalid plugin solver: `far` is for day 300"
+        );
+        assert!(matches!(solvers(old_version, None), Err(Error::Version(0))));
+        assert!(matches!(
+            load(Path::new("/nonexistent/plugin.so")),
+            Err(Error::Load(_))
+        ));
+    }
+}
@@ -0,0 +1,306 @@
+use std::io::{self, Read};
+use std::net::ToSocketAddrs;
+use std::panic::{self, AssertUnwindSafe};
+use std::time::Instant;
+
+use serde_json::{Value, json};
+use tiny_http::{Header, Method, Request, Response};
+
+use crate::parsing;
+use crate::solutions::{self, solution};
+use crate::trace::NoTrace;
+
+/// The largest input the server reads, in bytes.
+pub const MAX_BODY: u64 = 16 << 20;
+
+/// Answers puzzles over HTTP: `POST /solve/{day}` with the input as the body.
+///
+/// Query parameters are passed to the solver, except `part`, which restricts
+/// the response to one part.
+pub struct Server {
+    inner: tiny_http::Server,
+}
+
+impl Server {
+    pub fn bind(addr: impl ToSocketAddrs) -> io::Result<Self> {
+        let inner = tiny_http::Server::http(addr).map_err(io::Error::other)?;
+        Ok(Self { inner })
+    }
+
+    pub fn port(&self) -> u16 {
+        self.inner
+            .server_addr()
+            .to_ip()
+            .map_or(0, |addr| addr.port())
+    }
+
+    /// Answers requests until waiting for one fails. A request that fails
+    /// on its own, say because the client hung up, is only logged.
+    pub fn run(&self) -> io::Result<()> {
+        loop {
+            let request = self.inner.recv()?;
+            let url = request.url().to_string();
+            if let Err(e) = answer(request) {
+                eprintln!("{url}: {e}");
+            }
+        }
+    }
+
+    /// Waits for one request and answers it.
+    pub fn handle_next(&self) -> io::Result<()> {
+        answer(self.inner.recv()?)
+    }
+}
+
+/// Answers `request`, refusing bodies over [`MAX_BODY`].
+fn answer(mut request: Request) -> io::Result<()> {
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY + 1)
+        .read_to_end(&mut body)?;
+    if body.len() as u64 > MAX_BODY {
+        let (status, value) = error(
+            413,
+            "input",
+            format!("input is larger than {MAX_BODY} bytes"),
+        );
+        return respond(request, status, value);
+    }
+    let (status, value) = handle(request.method(), request.url(), &body);
+    respond(request, status, value)
+}
+
+fn respond(request: Request, status: u16, value: Value) -> io::Result<()> {
+    let header = Header::from_bytes("Content-Type", "application/json").unwrap();
+    let response = Response::from_string(value.to_string())
+        .with_status_code(status)
+        .with_header(header);
+    request.respond(response)
+}
+
+/// Routes one request, returning the status code and JSON body.
+pub fn handle(method: &Method, url: &str, body: &[u8]) -> (u16, Value) {
+    let (path, query) = url.split_once('?').unwrap_or((url, ""));
+    let Some(day) = path.strip_prefix("/solve/") else {
+        return error(404, "not_found", format!("no route for `{path}`"));
+    };
+    if *method != Method::Post {
+        return error(405, "method", format!("use POST, not {method}"));
+    }
+    let Some(solver) = day.parse().ok().and_then(solutions::solver) else {
+        return error(404, "not_found", format!("no solver for day `{day}`"));
+    };
+    let Ok(input) = std::str::from_utf8(body) else {
+        return error(400, "input", "input is not valid UTF-8".to_string());
+    };
+
+    let mut params = solution::Params::new();
+    let mut part = None;
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let Some(pair) = decode(pair) else {
+            return error(
+                400,
+                "param",
+                format!("`{pair}` is not percent-encoded UTF-8"),
+            );
+        };
+        let (key, value) = match solution::Params::parse_pair(&pair) {
+            Ok(pair) => pair,
+            Err(e) => return error(400, "param", e.to_string()),
+        };
+        match (key.as_str(), value.as_str()) {
+            ("part", "1" | "2") => part = Some(value),
+            ("part", _) => return error(400, "param", format!("no part `{value}`")),
+            _ => params.set(key, value),
+        }
+    }
+
+    let input = parsing::normalize(input);
+    let now = Instant::now();
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        solver.solve_with(&input, &params, &mut NoTrace)
+    }));
+    let elapsed = now.elapsed();
+
+    match result {
+        Ok(Ok(solution)) => {
+            let mut value = json!({
+                "day": day.parse::<u8>().unwrap(),
+                "elapsed_us": elapsed.as_micros() as u64,
+            });
+            if part.as_deref() != Some("2") {
+                value["part1"] = json!(solution.part1);
+            }
+            if part.as_deref() != Some("1") {
+                value["part2"] = json!(solution.part2);
+            }
+            (200, value)
+        }
+        Ok(Err(solution::Error::Parse(e))) => (
+            400,
+            json!({
+                "error": "parse",
+                "line": e.line,
+                "column": e.column,
+                "message": e.message,
+            }),
+        ),
+        Ok(Err(e @ solution::Error::Overflow(_))) => error(422, "overflow", e.to_string()),
+        Ok(Err(e @ solution::Error::Param(_))) => error(400, "param", e.to_string()),
+        Ok(Err(e @ solution::Error::NoSolution(_))) => error(422, "no_solution", e.to_string()),
+        Ok(Err(e @ solution::Error::Plugin(_))) => error(500, "plugin", e.to_string()),
+        Err(_) => error(500, "panic", "the solver panicked".to_string()),
+    }
+}
+
+/// Decodes a `key=value` pair from a query string, where `+` is a space and
+/// `%` starts a byte in hex.
+fn decode(pair: &str) -> Option<String> {
+    let mut bytes = Vec::with_capacity(pair.len());
+    let mut rest = pair.as_bytes();
+    while let Some((byte, tail)) = rest.split_first() {
+        rest = tail;
+        match byte {
+            b'+' => bytes.push(b' '),
+            b'%' => {
+                let hex = std::str::from_utf8(rest.get(..2)?).ok()?;
+                bytes.push(u8::from_str_radix(hex, 16).ok()?);
+                rest = &rest[2..];
+            }
+            _ => bytes.push(*byte),
+        }
+    }
+    String::from_utf8(bytes).ok()
+}
+
+fn error(status: u16, kind: &str, message: String) -> (u16, Value) {
+    (status, json!({ "error": kind, "message": message }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpStream;
+    use std::thread;
+
+    const DAY1: &str = "L68\nL30\nR48\nL5\nR60\nL55\nL1\nL99\nR14\nL82";
+
+    #[test]
+    fn test_solve() {
+        let (status, value) = handle(&Method::Post, "/solve/1", DAY1.as_bytes());
+        assert_eq!(status, 200);
+        assert_eq!(value["day"], 1);
+        assert_eq!(value["part1"], "3");
+        assert_eq!(value["part2"], "6");
+        assert!(value["elapsed_us"].is_u64());
+
+        let (_, value) = handle(&Method::Post, "/solve/1?part=2", DAY1.as_bytes());
+        assert_eq!(value["part1"], Value::Null);
+        assert_eq!(value["part2"], "6");
+    }
+
+    #[test]
+    fn test_decode() {
+        assert_eq!(
+            decode("connections=1%30").as_deref(),
+            Some("connections=10")
+        );
+        assert_eq!(decode("a+b=%E2%9C%93").as_deref(), Some("a b=✓"));
+        assert_eq!(decode("bad=%4"), None);
+        assert_eq!(decode("bad=%zz"), None);
+        assert_eq!(decode("bad=%ff"), None);
+
+        let input = "162,817,812\n57,618,57\n906,360,560";
+        let (status, encoded) = handle(&Method::Post, "/solve/8?connections=%31", input.as_bytes());
+        assert_eq!(status, 200);
+        let (_, plain) = handle(&Method::Post, "/solve/8?connections=1", input.as_bytes());
+        assert_eq!(encoded["part1"], plain["part1"]);
+        assert_eq!(handle(&Method::Post, "/solve/1?part=%", b"").0, 400);
+    }
+
+    #[test]
+    fn test_errors() {
+        let (status, value) = handle(&Method::Post, "/solve/1", b"L68\nX30");
+        assert_eq!(status, 400);
+        assert_eq!(value["error"], "parse");
+        assert_eq!(value["line"], 2);
+        assert_eq!(value["column"], 1);
+
+        assert_eq!(handle(&Method::Get, "/solve/1", b"").0, 405);
+        assert_eq!(handle(&Method::Post, "/solve/42", b"").0, 404);
+        assert_eq!(handle(&Method::Post, "/answers", b"").0, 404);
+        assert_eq!(handle(&Method::Post, "/solve/1?part=3", b"").0, 400);
+        assert_eq!(handle(&Method::Post, "/solve/1", &[0xff]).0, 400);
+
+        let (status, value) = handle(&Method::Post, "/solve/8?connections=many", b"1,2,3\n4,5,6");
+        assert_eq!(status, 400);
+        assert_eq!(value["error"], "param");
+    }
+
+    /// Sends `body` to `/solve/1` on a server on localhost and returns the
+    /// raw response.
+    fn post_to_localhost(body: &str) -> String {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let port = server.port();
+        let handle = thread::spawn(move || server.handle_next().unwrap());
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST /solve/1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+            body.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        handle.join().unwrap();
+        response
+    }
+
+    #[test]
+    fn test_localhost() {
+        let response = post_to_localhost(DAY1);
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let value: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value["part1"], "3");
+    }
+
+    #[test]
+    fn test_run_survives_hang_ups() {
+        let server = Server::bind("127.0.0.1:0").unwrap();
+        let port = server.port();
+        thread::spawn(move || server.run());
+
+        // Promise a body, send half of it and hang up.
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST /solve/1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: 100\r\n\r\nL68"
+        )
+        .unwrap();
+        drop(stream);
+
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST /solve/1 HTTP/1.1\r\nHost: localhost\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{DAY1}",
+            DAY1.len()
+        )
+        .unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        assert!(response.starts_with("HTTP/1.1 200"), "{response}");
+    }
+
+    #[test]
+    fn test_body_limit() {
+        let response = post_to_localhost(&"1".repeat(MAX_BODY as usize + 1));
+        assert!(response.starts_with("HTTP/1.1 413"), "{response}");
+        let body = response.split("\r\n\r\n").nth(1).unwrap();
+        let value: Value = serde_json::from_str(body).unwrap();
+        assert_eq!(value["error"], "input");
+    }
+}
@@ -1,25 +1,40 @@
+use crate::parsing::{self, ParseError};
 use crate::solutions::solution;
+use crate::trace::Trace;
 
 pub struct Day0Solver;
 impl solution::Solver for Day0Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        let values: Vec<&str> = input.lines().collect();
-        solution::Solution {
-            part1: values[0].to_string(),
-            part2: values[1].to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let values: Vec<&str> = parsing::lines(input).map(|l| l.text()).collect();
+        match values[..] {
+            [part1, part2, ..] => Ok(solution::Solution {
+                part1: part1.to_string(),
+                part2: part2.to_string(),
+            }),
+            _ => Err(ParseError::end_of_input(input, "expected two lines").into()),
         }
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Solver, assert_line_ending_variants};
     use super::*;
 
     #[test]
     fn test_solve() {
-        let solution = Day0Solver.solve("1\n2");
+        let solution = Day0Solver.solve("1\n2").unwrap();
         assert_eq!(solution.part1, "1");
         assert_eq!(solution.part2, "2");
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day0Solver, "1\n2");
+    }
 }
@@ -1,108 +1,581 @@
+use crate::answer::Overflow;
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::Trace;
+use std::io::{self, BufRead};
+use std::str::FromStr;
+use std::{error, fmt};
 
-enum Rotation {
-    Left(i32),
-    Right(i32),
+#[derive(Debug, PartialEq)]
+pub enum Rotation {
+    Left(u64),
+    Right(u64),
 }
 
-struct State {
-    position: i32,
-    count: i32,
+impl fmt::Display for Rotation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Rotation::Left(val) => write!(f, "L{val}"),
+            Rotation::Right(val) => write!(f, "R{val}"),
+        }
+    }
 }
 
-pub struct Day1Solver;
+/// A dial numbered `0..size` that counts how often it lands on each of its
+/// targets, and how often it points at one while turning.
+#[derive(Debug, Clone)]
+pub struct Dial {
+    size: u64,
+    targets: Vec<u64>,
+    landed: Vec<u64>,
+    passed: Vec<u128>,
+    /// The start position, then the position after each rotation.
+    history: Vec<u64>,
+    keep_history: bool,
+}
 
-fn part1(rotations: &[Rotation]) -> State {
-    let start = State {
-        position: 50,
-        count: 0,
-    };
+impl Dial {
+    /// The puzzle's dial: a hundred positions, starting at 50, watching 0.
+    pub fn puzzle() -> Self {
+        Self::new(100, 50, &[0]).unwrap()
+    }
 
-    rotations.iter().fold(start, |acc, cmd| {
-        let new_pos = match cmd {
-            Rotation::Left(val) => (acc.position - val).rem_euclid(100),
-            Rotation::Right(val) => (acc.position + val).rem_euclid(100),
-        };
+    pub fn new(size: u64, start: u64, targets: &[u64]) -> Result<Self, solution::Error> {
+        if size == 0 {
+            return Err(solution::Error::Param(
+                "a dial needs at least one position".to_string(),
+            ));
+        }
+        if let Some(position) = std::iter::once(&start).chain(targets).find(|p| **p >= size) {
+            return Err(solution::Error::Param(format!(
+                "a dial of size {size} has no position {position}"
+            )));
+        }
+        if let Some((_, target)) = targets
+            .iter()
+            .enumerate()
+            .find(|(idx, target)| targets[..*idx].contains(target))
+        {
+            return Err(solution::Error::Param(format!(
+                "target {target} is given more than once"
+            )));
+        }
+        Ok(Self {
+            size,
+            targets: targets.to_vec(),
+            landed: vec![0; targets.len()],
+            passed: vec![0; targets.len()],
+            history: vec![start],
+            keep_history: true,
+        })
+    }
+
+    /// Stops recording every position, so the dial uses the same memory
+    /// however many rotations it makes; [`Dial::history`] then only holds the
+    /// current position.
+    pub fn without_history(mut self) -> Self {
+        self.keep_history = false;
+        self.history.drain(..self.history.len() - 1);
+        self
+    }
+
+    pub fn turn(&mut self, rotation: &Rotation) -> Turn {
+        let (size, from) = (u128::from(self.size), u128::from(self.position()));
+        let (left, distance) = direction(rotation);
+        let distance = u128::from(distance);
 
-        let new_count = match new_pos {
-            0 => acc.count + 1,
-            _ => acc.count,
+        let clicks: Vec<u64> = self
+            .targets
+            .iter()
+            .map(|target| {
+                let target = u128::from(*target);
+                // Clicks until the dial first points at the target, a full
+                // turn if it already does.
+                let gap = match left {
+                    true => (from + size - target) % size,
+                    false => (target + size - from) % size,
+                };
+                let first = if gap == 0 { size } else { gap };
+                match distance >= first {
+                    true => ((distance - first) / size + 1) as u64,
+                    false => 0,
+                }
+            })
+            .collect();
+
+        let to = match left {
+            true => (from + size - distance % size) % size,
+            false => (from + distance % size) % size,
         };
+        self.record(to as u64, &clicks)
+    }
+
+    /// The reference for [`Dial::turn`], moving one click at a time, so it
+    /// takes time in proportion to the distance.
+    pub fn turn_by_clicks(&mut self, rotation: &Rotation) -> Turn {
+        let (left, distance) = direction(rotation);
+        let mut position = self.position();
+        let mut clicks = vec![0; self.targets.len()];
+
+        for _ in 0..distance {
+            position = match (left, position) {
+                (true, 0) => self.size - 1,
+                (true, _) => position - 1,
+                (false, _) if position + 1 == self.size => 0,
+                (false, _) => position + 1,
+            };
+            for (count, target) in clicks.iter_mut().zip(&self.targets) {
+                if position == *target {
+                    *count += 1;
+                }
+            }
+        }
+        self.record(position, &clicks)
+    }
+
+    /// Moves to `to`, having clicked onto each target `clicks` times.
+    fn record(&mut self, to: u64, clicks: &[u64]) -> Turn {
+        let from = self.position();
+        for (idx, target) in self.targets.iter().enumerate() {
+            self.passed[idx] += u128::from(clicks[idx]);
+            if to == *target {
+                self.landed[idx] += 1;
+            }
+        }
+        match self.keep_history {
+            true => self.history.push(to),
+            false => self.history[0] = to,
+        }
+        Turn {
+            from,
+            to,
+            clicks: clicks.iter().sum(),
+        }
+    }
+
+    pub fn position(&self) -> u64 {
+        *self.history.last().unwrap()
+    }
+
+    pub fn targets(&self) -> &[u64] {
+        &self.targets
+    }
+
+    /// How many rotations ended on `target`, 0 if it isn't a target.
+    pub fn landed(&self, target: u64) -> u64 {
+        self.count(&self.landed, target)
+    }
+
+    /// How many clicks left the dial pointing at `target`, including those
+    /// that ended a rotation; 0 if it isn't a target.
+    pub fn passed(&self, target: u64) -> u128 {
+        self.count(&self.passed, target)
+    }
+
+    /// Landings on any target: part 1's answer for the puzzle's dial.
+    pub fn total_landed(&self) -> u64 {
+        self.landed.iter().sum()
+    }
+
+    /// Clicks onto any target: part 2's answer for the puzzle's dial.
+    pub fn total_passed(&self) -> u128 {
+        self.passed.iter().sum()
+    }
+
+    fn count<T: Copy + Default>(&self, counts: &[T], target: u64) -> T {
+        self.targets
+            .iter()
+            .position(|t| *t == target)
+            .map_or(T::default(), |idx| counts[idx])
+    }
+
+    /// The start position, then the position after each rotation, unless
+    /// [`Dial::without_history`].
+    pub fn history(&self) -> &[u64] {
+        &self.history
+    }
+}
+
+/// What one rotation did to a [`Dial`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Turn {
+    pub from: u64,
+    pub to: u64,
+    /// Clicks onto any target, including one that ends the rotation. Each
+    /// click lands on one position and targets are distinct, so this is at
+    /// most the rotation's distance.
+    pub clicks: u64,
+}
+
+/// Whether `rotation` turns left, and how far.
+fn direction(rotation: &Rotation) -> (bool, u64) {
+    match rotation {
+        Rotation::Left(val) => (true, *val),
+        Rotation::Right(val) => (false, *val),
+    }
+}
+
+/// Every rotation in `input` with its line and what it did, so a part 2
+/// answer can be checked line by line.
+pub fn audit(
+    input: &str,
+    params: &solution::Params,
+) -> Result<Vec<(usize, Rotation, Turn)>, solution::Error> {
+    let mut dial = dial(params)?;
+    parsing::lines(input)
+        .map(|line| {
+            let rotation = rotation(line)?;
+            let turn = dial.turn(&rotation);
+            Ok((line.line(), rotation, turn))
+        })
+        .collect()
+}
+
+/// Why [`stream`] stopped before the end of its input.
+#[derive(Debug)]
+pub enum StreamError {
+    Read(io::Error),
+    Parse(ParseError),
+}
+
+impl fmt::Display for StreamError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StreamError::Read(e) => write!(f, "cannot read input: {e}"),
+            StreamError::Parse(e) => write!(f, "invalid input at {e}"),
+        }
+    }
+}
+
+impl error::Error for StreamError {}
+
+impl From<io::Error> for StreamError {
+    fn from(value: io::Error) -> Self {
+        StreamError::Read(value)
+    }
+}
 
-        State {
-            position: new_pos,
-            count: new_count,
+impl From<ParseError> for StreamError {
+    fn from(value: ParseError) -> Self {
+        StreamError::Parse(value)
+    }
+}
+
+/// Turns `dial` by each rotation read from `reader` a line at a time, so the
+/// input can be far larger than memory when the dial is
+/// [`Dial::without_history`]. Calls `progress` with the number of rotations
+/// so far after every `every` of them, never if it is 0, and returns the
+/// number at the end.
+pub fn stream(
+    mut reader: impl BufRead,
+    dial: &mut Dial,
+    every: u64,
+    mut progress: impl FnMut(u64, &Dial),
+) -> Result<u64, StreamError> {
+    let mut line = String::new();
+    let (mut number, mut rotations, mut blank) = (0, 0, None);
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            return Ok(rotations);
+        }
+        number += 1;
+        let text = line.trim_end();
+        if text.is_empty() {
+            blank = blank.or(Some(number));
+            continue;
+        }
+        if let Some(blank) = blank {
+            // Blank lines may only trail, as with `parsing::lines`.
+            rotation(Token::new("", blank, 1))?;
         }
-    })
+
+        dial.turn(&rotation(Token::new(text, number, 1))?);
+        rotations += 1;
+        if rotations.is_multiple_of(every) {
+            progress(rotations, dial);
+        }
+    }
+}
+
+/// What [`shortest_rotations`] keeps to a minimum.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shortest {
+    Rotations,
+    Clicks,
+}
+
+impl FromStr for Shortest {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "rotations" => Ok(Shortest::Rotations),
+            "clicks" => Ok(Shortest::Clicks),
+            _ => Err(format!("expected `rotations` or `clicks`, found `{s}`")),
+        }
+    }
+}
+
+/// A shortest list of rotations, each at least one click long, that turns a
+/// dial of `size` from `start` so it lands on `target` `landings` times and
+/// points at it `passes` times in all: the counts parts 1 and 2 would give.
+pub fn shortest_rotations(
+    size: u64,
+    start: u64,
+    target: u64,
+    landings: u64,
+    passes: u64,
+    shortest: Shortest,
+) -> Result<Vec<Rotation>, solution::Error> {
+    Dial::new(size, start, &[target])?;
+    let none = |message: &str| Err(solution::Error::NoSolution(message.to_string()));
+    if landings > passes {
+        return none("a dial can't land on a target more often than it points at it");
+    }
+    if size == 1 && landings == 0 && passes > 0 {
+        return none("every rotation of a one-position dial lands on it");
+    }
+
+    let rotations = match (size, shortest) {
+        _ if passes == 0 => vec![],
+        // Every click lands, so the rotations and clicks are both fixed.
+        (1, _) => std::iter::once((false, passes - landings + 1))
+            .chain(std::iter::repeat_n((false, 1), landings as usize - 1))
+            .collect(),
+        (_, Shortest::Rotations) => fewest_rotations(size, start, target, landings, passes)?,
+        (_, Shortest::Clicks) => fewest_clicks(size, start, target, landings, passes),
+    };
+    Ok(rotations
+        .into_iter()
+        .map(|(left, distance)| match left {
+            true => Rotation::Left(distance),
+            false => Rotation::Right(distance),
+        })
+        .collect())
 }
 
-fn part2(rotations: &[Rotation]) -> State {
-    let start = State {
-        position: 50,
-        count: 0,
+/// Clicks from `from` to `to` turning right, and turning left.
+fn gaps(size: u64, from: u64, to: u64) -> (u64, u64) {
+    let right = match to >= from {
+        true => to - from,
+        false => size - (from - to),
     };
+    (right, if right == 0 { 0 } else { size - right })
+}
 
-    rotations.iter().fold(start, |acc, cmd| {
-        let (full_rotations, rotation) = match cmd {
-            Rotation::Left(val) => (val / 100, val.rem_euclid(100)),
-            Rotation::Right(val) => (val / 100, val.rem_euclid(100)),
-        };
+/// Rotations as puzzle input, one per line.
+pub fn format_rotations(rotations: &[Rotation]) -> String {
+    rotations
+        .iter()
+        .map(Rotation::to_string)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-        let (new_pos, rot) = match cmd {
-            Rotation::Right(_) => {
-                let rot = if rotation + acc.position >= 100 { 1 } else { 0 };
-                ((acc.position + rotation).rem_euclid(100), rot)
-            }
-            Rotation::Left(_) => {
-                let rot = if acc.position != 0 && (acc.position - rotation <= 0) {
-                    1
-                } else {
-                    0
-                };
-                ((acc.position - rotation).rem_euclid(100), rot)
-            }
-        };
+/// One rotation per landing, or a single one if there are none, with every
+/// pass beyond those taken as full turns on the first.
+fn fewest_rotations(
+    size: u64,
+    start: u64,
+    target: u64,
+    landings: u64,
+    passes: u64,
+) -> Result<Vec<(bool, u64)>, Overflow> {
+    let (right, left) = gaps(size, start, target);
+    let (towards_left, first) = match (right, left) {
+        (0, _) => (false, size),
+        (right, left) if left < right => (true, left),
+        (right, _) => (false, right),
+    };
+    let distance = (passes - landings.max(1))
+        .checked_mul(size)
+        .and_then(|turns| turns.checked_add(first))
+        // Without landings, stop one click past the target.
+        .and_then(|distance| distance.checked_add(u64::from(landings == 0)))
+        .ok_or(Overflow)?;
 
-        State {
-            position: new_pos,
-            count: acc.count + full_rotations + rot,
+    let mut rotations = vec![(towards_left, distance)];
+    rotations.extend(std::iter::repeat_n(
+        (false, size),
+        landings.saturating_sub(1) as usize,
+    ));
+    Ok(rotations)
+}
+
+/// Reaches the target the short way, then rocks across it for two clicks a
+/// pass, ending a rotation on it for each of the last `landings` passes.
+fn fewest_clicks(
+    size: u64,
+    start: u64,
+    target: u64,
+    landings: u64,
+    passes: u64,
+) -> Vec<(bool, u64)> {
+    // Each rotation's direction, length and whether it has ended.
+    let mut rotations: Vec<(bool, u64, bool)> = vec![];
+    let extend = |rotations: &mut Vec<(bool, u64, bool)>, left: bool| match rotations.last_mut() {
+        Some((l, clicks, false)) if *l == left => *clicks += 1,
+        _ => rotations.push((left, 1, false)),
+    };
+
+    let (right, left) = gaps(size, start, target);
+    let mut towards_left = match (right, left) {
+        // Step off and back.
+        (0, _) => {
+            rotations.push((false, 1, true));
+            rotations.push((true, 1, false));
+            true
+        }
+        (right, left) if left < right => {
+            rotations.push((true, left, false));
+            true
+        }
+        (right, _) => {
+            rotations.push((false, right, false));
+            false
+        }
+    };
+
+    for pass in 1..=passes {
+        if pass > 1 {
+            extend(&mut rotations, towards_left);
+            rotations.last_mut().unwrap().2 = true;
+            towards_left = !towards_left;
+            extend(&mut rotations, towards_left);
+        }
+        if pass > passes - landings {
+            rotations.last_mut().unwrap().2 = true;
         }
-    })
+    }
+    if landings == 0 {
+        extend(&mut rotations, towards_left);
+    }
+
+    rotations
+        .into_iter()
+        .map(|(left, clicks, _)| (left, clicks))
+        .collect()
+}
+
+pub struct Day1Solver;
+
+fn turn_all(dial: &mut Dial, rotations: &[Rotation], trace: &mut dyn Trace) {
+    for rotation in rotations {
+        let turn = dial.turn(rotation);
+        trace.step(
+            "rotation",
+            format_args!("{rotation} -> {}, {} zero clicks", turn.to, turn.clicks),
+        );
+    }
 }
 
 impl solution::Solver for Day1Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        let parsed_input: Vec<Rotation> = input.lines().map(rotation).collect();
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let parsed_input: Vec<Rotation> = parsing::lines(input)
+            .map(rotation)
+            .collect::<Result<_, _>>()?;
 
-        let part1_solution = part1(&parsed_input);
-        let part2_solution = part2(&parsed_input);
+        let mut dial = dial(params)?;
+        turn_all(&mut dial, &parsed_input, trace);
 
-        solution::Solution {
-            part1: part1_solution.count.to_string(),
-            part2: part2_solution.count.to_string(),
-        }
+        Ok(solution::Solution {
+            part1: dial.total_landed().to_string(),
+            part2: dial.total_passed().to_string(),
+        })
     }
 }
 
-fn rotation(value: &str) -> Rotation {
-    let rotation = value.chars().next();
-    let value: i32 = value[1..].parse().unwrap();
+/// The dial described by the `size`, `start` and comma-separated `targets`
+/// params, the puzzle's by default.
+pub fn dial(params: &solution::Params) -> Result<Dial, solution::Error> {
+    let targets: Vec<u64> = params
+        .get("targets", "0".to_string())?
+        .split(',')
+        .map(|target| {
+            target
+                .trim()
+                .parse()
+                .map_err(|_| solution::Error::Param(format!("`{target}` is not a valid target")))
+        })
+        .collect::<Result<_, _>>()?;
+    Dial::new(params.get("size", 100)?, params.get("start", 50)?, &targets)
+}
 
-    match rotation {
-        Some('L') => Rotation::Left(value),
-        Some('R') => Rotation::Right(value),
-        _ => panic!("Unexpected rotation"),
+fn rotation(line: Token) -> Result<Rotation, ParseError> {
+    let distance = |value: Token| match value.text().starts_with('-') {
+        true => Err(value.error("expected a non-negative distance")),
+        false => value.parse(),
+    };
+    if let Ok(value) = line.strip_prefix("L") {
+        Ok(Rotation::Left(distance(value)?))
+    } else if let Ok(value) = line.strip_prefix("R") {
+        Ok(Rotation::Right(distance(value)?))
+    } else {
+        Err(line.error("expected a rotation starting with `L` or `R`"))
+    }
+}
+
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed(Vec<Rotation>);
+
+    /// `size` rotations of up to a thousand clicks.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(1);
+        (0..size)
+            .map(|_| {
+                let direction = if rng.chance(50) { 'L' } else { 'R' };
+                format!("{direction}{}", rng.range(1, 1000))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(
+            parsing::lines(input)
+                .map(rotation)
+                .collect::<Result<_, _>>()?,
+        ))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    /// Both parts turn the whole dial, so they differ only in what they read.
+    pub fn part1(parsed: &Parsed) -> u64 {
+        let mut dial = Dial::puzzle();
+        turn_all(&mut dial, &parsed.0, &mut NoTrace);
+        dial.total_landed()
+    }
+
+    pub fn part2(parsed: &Parsed) -> u128 {
+        let mut dial = Dial::puzzle();
+        turn_all(&mut dial, &parsed.0, &mut NoTrace);
+        dial.total_passed()
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Params, Solver, assert_line_ending_variants};
     use super::*;
+    use crate::trace::{NoTrace, RecordTrace};
+    use proptest::prelude::*;
 
-    #[test]
-    fn test_solve() {
-        let input = r#"L68
+    const INPUT: &str = r#"L68
 L30
 R48
 L5
@@ -113,8 +586,413 @@ L99
 R14
 L82"#;
 
-        let solution = Day1Solver.solve(input);
+    #[test]
+    fn test_solve() {
+        let solution = Day1Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "3");
         assert_eq!(solution.part2, "6");
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day1Solver, INPUT);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut trace = RecordTrace::default();
+        Day1Solver
+            .solve_with("L68\nL30\nR48", &Params::new(), &mut trace)
+            .unwrap();
+        assert_eq!(
+            trace.details("rotation"),
+            vec![
+                "L68 -> 82, 1 zero clicks",
+                "L30 -> 52, 0 zero clicks",
+                "R48 -> 0, 1 zero clicks",
+            ]
+        );
+    }
+
+    /// At least a click each: turning nothing while on a target lands on it
+    /// without passing it.
+    fn rotations() -> impl Strategy<Value = Rotation> {
+        prop_oneof![
+            (1..1000u64).prop_map(Rotation::Left),
+            (1..1000u64).prop_map(Rotation::Right),
+        ]
+    }
+
+    #[test]
+    fn test_dial() {
+        let mut dial = Dial::new(10, 5, &[0, 3]).unwrap();
+        for rotation in [Rotation::Right(5), Rotation::Left(12), Rotation::Right(30)] {
+            dial.turn(&rotation);
+        }
+        assert_eq!(dial.history(), &[5, 0, 8, 8]);
+        assert_eq!(dial.position(), 8);
+        assert_eq!((dial.landed(0), dial.passed(0)), (1, 5));
+        assert_eq!((dial.landed(3), dial.passed(3)), (0, 4));
+        assert_eq!(dial.passed(7), 0);
+
+        let params = Params::from([("size", "10"), ("start", "5"), ("targets", "0,3")]);
+        let solution = Day1Solver.solve_with("R5\nL12\nR30", &params, &mut NoTrace);
+        assert_eq!(solution.unwrap().part2, "9");
+
+        let error = |param: (&str, &str)| {
+            let params = Params::from([param]);
+            Day1Solver
+                .solve_with("R5", &params, &mut NoTrace)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(("size", "0")),
+            "invalid parameter: a dial needs at least one position"
+        );
+        assert_eq!(
+            error(("targets", "0,100")),
+            "invalid parameter: a dial of size 100 has no position 100"
+        );
+        assert_eq!(
+            error(("targets", "0,3,0")),
+            "invalid parameter: target 0 is given more than once"
+        );
+        assert_eq!(
+            error(("targets", "x")),
+            "invalid parameter: `x` is not a valid target"
+        );
+    }
+
+    #[test]
+    fn test_clicks_fit_the_distance() {
+        assert!(Dial::new(1, 0, &[0, 0]).is_err());
+        let mut dial = Dial::new(1, 0, &[0]).unwrap();
+        assert_eq!(dial.turn(&Rotation::Right(u64::MAX)).clicks, u64::MAX);
+        let mut dial = Dial::new(2, 0, &[0, 1]).unwrap();
+        assert_eq!(dial.turn(&Rotation::Left(u64::MAX)).clicks, u64::MAX);
+        assert_eq!(dial.total_passed(), u128::from(u64::MAX));
+    }
+
+    /// A reader that fails after its first line.
+    struct Failing(bool);
+
+    impl io::Read for Failing {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if std::mem::replace(&mut self.0, true) {
+                return Err(io::Error::other("disk on fire"));
+            }
+            buf[..4].copy_from_slice(b"L68\n");
+            Ok(4)
+        }
+    }
+
+    #[test]
+    fn test_stream() {
+        let input = format!("{}\r\n\n", INPUT.replace('\n', "\r\n"));
+        let mut dial = Dial::puzzle().without_history();
+        let mut reports = vec![];
+        let rotations = stream(input.as_bytes(), &mut dial, 4, |rotations, dial| {
+            reports.push((rotations, dial.total_landed(), dial.total_passed()))
+        });
+        assert_eq!(rotations.unwrap(), 10);
+        assert_eq!(reports, vec![(4, 1, 2), (8, 3, 5)]);
+        assert_eq!((dial.total_landed(), dial.total_passed()), (3, 6));
+        assert_eq!(dial.history(), &[32]);
+
+        let error = |input: &[u8]| {
+            stream(input, &mut Dial::puzzle(), 0, |_, _| unreachable!())
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(b"L68\n\nR48"),
+            "invalid input at line 2, column 1: expected a rotation starting with `L` or `R`"
+        );
+        assert_eq!(
+            error(b"L68\nR4x"),
+            Day1Solver.solve("L68\nR4x").unwrap_err().to_string()
+        );
+        let failing = io::BufReader::new(Failing(false));
+        let error = stream(failing, &mut Dial::puzzle(), 0, |_, _| ()).unwrap_err();
+        assert_eq!(error.to_string(), "cannot read input: disk on fire");
+    }
+
+    #[test]
+    fn test_audit() {
+        let turns = audit(INPUT, &Params::new()).unwrap();
+        assert_eq!(turns.len(), 10);
+        assert_eq!(
+            turns[0],
+            (
+                1,
+                Rotation::Left(68),
+                Turn {
+                    from: 50,
+                    to: 82,
+                    clicks: 1
+                }
+            )
+        );
+        let total: u64 = turns.iter().map(|(_, _, turn)| turn.clicks).sum();
+        assert_eq!(total.to_string(), Day1Solver.solve(INPUT).unwrap().part2);
+    }
+
+    /// Compares one rotation of [`Dial::turn`] with [`Dial::turn_by_clicks`].
+    fn assert_turns_agree(dial: &Dial, rotation: &Rotation) {
+        let (mut fast, mut slow) = (dial.clone(), dial.clone());
+        assert_eq!(
+            fast.turn(rotation),
+            slow.turn_by_clicks(rotation),
+            "{rotation} from {}",
+            dial.position()
+        );
+        for target in dial.targets() {
+            assert_eq!(fast.passed(*target), slow.passed(*target));
+            assert_eq!(fast.landed(*target), slow.landed(*target));
+        }
+    }
+
+    #[test]
+    fn test_turn_matches_clicks_on_small_dials() {
+        for size in 1..=12u64 {
+            let targets: Vec<u64> = (0..size).collect();
+            for start in 0..size {
+                let dial = Dial::new(size, start, &targets).unwrap();
+                for distance in 0..=5 * size {
+                    assert_turns_agree(&dial, &Rotation::Left(distance));
+                    assert_turns_agree(&dial, &Rotation::Right(distance));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_turn_matches_clicks_on_puzzle_dial() {
+        for start in 0..100 {
+            let dial = Dial::new(100, start, &[0]).unwrap();
+            for distance in 0..=400 {
+                assert_turns_agree(&dial, &Rotation::Left(distance));
+                assert_turns_agree(&dial, &Rotation::Right(distance));
+            }
+        }
+    }
+
+    #[test]
+    fn test_large_rotations() {
+        let solution = Day1Solver.solve("R2147483647\nL2147483647").unwrap();
+        assert_eq!(solution.part2, "42949672");
+        let solution = Day1Solver.solve("R18446744073709551615").unwrap();
+        assert_eq!(solution.part2, "184467440737095516");
+
+        // Every click of a one-position dial passes its target, so the count
+        // outgrows u64.
+        let params = Params::from([("size", "1"), ("start", "0")]);
+        let input = "R18446744073709551615\nL18446744073709551615";
+        let solution = Day1Solver.solve_with(input, &params, &mut NoTrace);
+        assert_eq!(solution.unwrap().part2, "36893488147419103230");
+
+        assert!(Day1Solver.solve("R18446744073709551616").is_err());
+        assert_eq!(
+            Day1Solver.solve("L-5").unwrap_err().to_string(),
+            "invalid input at line 1, column 2: expected a non-negative distance"
+        );
+    }
+
+    /// Turns a dial by `rotations`, returning its landings and passes on
+    /// `target`.
+    fn counts(size: u64, start: u64, target: u64, rotations: &[Rotation]) -> (u64, u64) {
+        let mut dial = Dial::new(size, start, &[target]).unwrap();
+        turn_all(&mut dial, rotations, &mut NoTrace);
+        (dial.landed(target), dial.passed(target) as u64)
+    }
+
+    fn total_clicks(rotations: &[Rotation]) -> u64 {
+        rotations.iter().map(|rotation| direction(rotation).1).sum()
+    }
+
+    /// The fewest clicks reaching the counts, by breadth-first search over
+    /// single clicks and rotation ends.
+    fn fewest_clicks_by_search(
+        size: u64,
+        start: u64,
+        target: u64,
+        landings: u64,
+        passes: u64,
+    ) -> Option<u64> {
+        // Position, landings, passes, and the direction of the rotation under
+        // way: 0 between rotations, 1 left, 2 right.
+        let mut seen = std::collections::HashSet::new();
+        let mut queue = std::collections::VecDeque::from([((start, 0, 0, 0u8), 0)]);
+        while let Some((state @ (position, landed, passed, turning), clicks)) = queue.pop_front() {
+            if !seen.insert(state) {
+                continue;
+            }
+            if turning == 0 && (landed, passed) == (landings, passes) {
+                return Some(clicks);
+            }
+            if turning != 0 {
+                let landed = landed + u64::from(position == target);
+                if landed <= landings {
+                    queue.push_front(((position, landed, passed, 0), clicks));
+                }
+            }
+            for direction in [1, 2] {
+                if turning != 0 && turning != direction {
+                    continue;
+                }
+                let position = match direction {
+                    1 => (position + size - 1) % size,
+                    _ => (position + 1) % size,
+                };
+                let passed = passed + u64::from(position == target);
+                if passed <= passes {
+                    queue.push_back(((position, landed, passed, direction), clicks + 1));
+                }
+            }
+        }
+        None
+    }
+
+    #[test]
+    fn test_shortest_rotations() {
+        let rotations = shortest_rotations(100, 50, 0, 3, 6, Shortest::Rotations).unwrap();
+        assert_eq!(format_rotations(&rotations), "R350\nR100\nR100");
+
+        let rotations = shortest_rotations(100, 50, 0, 3, 6, Shortest::Clicks).unwrap();
+        assert_eq!(total_clicks(&rotations), 60);
+        let params = Params::from([("size", "100"), ("start", "50")]);
+        let solution = Day1Solver
+            .solve_with(&format_rotations(&rotations), &params, &mut NoTrace)
+            .unwrap();
+        assert_eq!(
+            (solution.part1.as_str(), solution.part2.as_str()),
+            ("3", "6")
+        );
+
+        let rotations = shortest_rotations(100, 80, 0, 0, 2, Shortest::Rotations).unwrap();
+        assert_eq!(rotations, vec![Rotation::Right(121)]);
+        assert!(
+            shortest_rotations(100, 50, 0, 0, 0, Shortest::Clicks)
+                .unwrap()
+                .is_empty()
+        );
+
+        let error = |size, landings, passes| {
+            shortest_rotations(size, 0, 0, landings, passes, Shortest::Clicks)
+                .unwrap_err()
+                .to_string()
+        };
+        assert_eq!(
+            error(100, 2, 1),
+            "no solution: a dial can't land on a target more often than it points at it"
+        );
+        assert_eq!(
+            error(1, 0, 1),
+            "no solution: every rotation of a one-position dial lands on it"
+        );
+        assert_eq!(
+            error(0, 0, 0),
+            "invalid parameter: a dial needs at least one position"
+        );
+        assert!(matches!(
+            shortest_rotations(u64::MAX, 0, 0, 1, 3, Shortest::Rotations),
+            Err(solution::Error::Overflow(_))
+        ));
+    }
+
+    #[test]
+    fn test_shortest_rotations_match_search() {
+        for size in 1..=5 {
+            for (start, target) in (0..size).flat_map(|s| (0..size).map(move |t| (s, t))) {
+                for passes in 0..=4 {
+                    for landings in 0..=passes + 1 {
+                        let fewest = fewest_clicks_by_search(size, start, target, landings, passes);
+                        let by_clicks = shortest_rotations(
+                            size,
+                            start,
+                            target,
+                            landings,
+                            passes,
+                            Shortest::Clicks,
+                        );
+                        let by_rotations = shortest_rotations(
+                            size,
+                            start,
+                            target,
+                            landings,
+                            passes,
+                            Shortest::Rotations,
+                        );
+                        let (Ok(by_clicks), Ok(by_rotations)) = (by_clicks, by_rotations) else {
+                            assert_eq!(fewest, None, "size {size} from {start} to {target}");
+                            continue;
+                        };
+                        assert_eq!(
+                            Some(total_clicks(&by_clicks)),
+                            fewest,
+                            "size {size} from {start} to {target}, {landings}/{passes}"
+                        );
+                        for rotations in [&by_clicks, &by_rotations] {
+                            assert_eq!(counts(size, start, target, rotations), (landings, passes));
+                        }
+                        let needed = landings.max(u64::from(passes > 0));
+                        assert_eq!(by_rotations.len() as u64, needed);
+                    }
+                }
+            }
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn prop_shortest_rotations_reach_counts(
+            size in 2..200u64,
+            start in 0..200u64,
+            target in 0..200u64,
+            passes in 0..50u64,
+            landings in 0..50u64,
+        ) {
+            let (start, target, landings) = (start % size, target % size, landings.min(passes));
+            for shortest in [Shortest::Rotations, Shortest::Clicks] {
+                let rotations =
+                    shortest_rotations(size, start, target, landings, passes, shortest).unwrap();
+                let text = format_rotations(&rotations);
+                let params = Params::from([
+                    ("size", size.to_string().as_str()),
+                    ("start", start.to_string().as_str()),
+                    ("targets", target.to_string().as_str()),
+                ]);
+                let solution = Day1Solver.solve_with(&text, &params, &mut NoTrace).unwrap();
+                prop_assert_eq!(solution.part1, landings.to_string());
+                prop_assert_eq!(solution.part2, passes.to_string());
+            }
+        }
+
+        #[test]
+        fn prop_rotation_round_trip(rotation in rotations()) {
+            let text = rotation.to_string();
+            prop_assert_eq!(super::rotation(Token::new(&text, 1, 1)).unwrap(), rotation);
+        }
+
+        #[test]
+        fn prop_passing_counts_landing(
+            rotations in prop::collection::vec(rotations(), 0..50),
+            size in 1..200u64,
+            targets in prop::collection::vec(0..200u64, 1..4),
+        ) {
+            let mut targets: Vec<u64> = targets.iter().map(|t| t % size).collect();
+            targets.sort_unstable();
+            targets.dedup();
+            let mut dial = Dial::new(size, 0, &targets).unwrap();
+            turn_all(&mut dial, &rotations, &mut NoTrace);
+
+            prop_assert_eq!(dial.history().len(), rotations.len() + 1);
+            for target in targets {
+                let landings = dial.history()[1..].iter().filter(|p| **p == target).count();
+                prop_assert_eq!(dial.landed(target), landings as u64);
+                prop_assert!(dial.passed(target) >= u128::from(dial.landed(target)));
+            }
+        }
+    }
 }
@@ -1,27 +1,71 @@
 use std::collections::{HashSet, VecDeque};
 use z3::{Optimize, SatResult, ast::Int};
 
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::Trace;
 
 pub struct Day10Solver;
 
+/// Solves part 1 by Gaussian elimination over GF(2) instead of a search.
+pub struct Day10EliminationSolver;
+
 impl solution::Solver for Day10Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
-        }
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let machines = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&machines, fewest_presses)?.to_string(),
+            part2: part2(&machines)?.to_string(),
+        })
+    }
+}
+
+impl solution::Solver for Day10EliminationSolver {
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let machines = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&machines, fewest_presses_elimination)?.to_string(),
+            part2: part2(&machines)?.to_string(),
+        })
     }
 }
 
-fn part2(input: &str) -> u64 {
-    let machines: Vec<Machine> = input.lines().map(Machine::from).collect();
-    machines.iter().map(fewest_joltages_min).sum()
+fn parse(input: &str) -> Result<Vec<Machine>, ParseError> {
+    parsing::lines(input).map(Machine::try_from).collect()
+}
+
+fn part2(machines: &[Machine]) -> Result<u64, solution::Error> {
+    machines
+        .iter()
+        .map(|machine| fewest_joltages_min(machine).ok_or_else(|| unreachable_target(machine)))
+        .sum()
+}
+
+fn part1(
+    machines: &[Machine],
+    fewest: fn(&Machine) -> Option<u64>,
+) -> Result<u64, solution::Error> {
+    machines
+        .iter()
+        .map(|machine| fewest(machine).ok_or_else(|| unreachable_target(machine)))
+        .sum()
 }
 
-fn part1(input: &str) -> u64 {
-    let machines: Vec<Machine> = input.lines().map(Machine::from).collect();
-    machines.iter().map(fewest_presses).sum()
+fn unreachable_target(machine: &Machine) -> solution::Error {
+    solution::Error::NoSolution(format!(
+        "no button presses reach the target of machine {}",
+        machine.line
+    ))
 }
 
 fn positions(button: u64) -> Vec<usize> {
@@ -40,9 +84,12 @@ fn positions(button: u64) -> Vec<usize> {
     res
 }
 
-fn fewest_joltages_min(machine: &Machine) -> u64 {
+fn fewest_joltages_min(machine: &Machine) -> Option<u64> {
     let joltages = &machine.joltages;
     let buttons: Vec<Vec<usize>> = machine.buttons.iter().map(|b| positions(*b)).collect();
+    if buttons.is_empty() {
+        return joltages.iter().all(|j| *j == 0).then_some(0);
+    }
 
     let opt = Optimize::new();
     let total_presses = Int::fresh_const("total_presses");
@@ -60,8 +107,15 @@ fn fewest_joltages_min(machine: &Machine) -> u64 {
                 terms.push(button_presses[i].clone());
             }
         }
+        // z3 can't add up nothing, and no presses only ever make zero.
+        if terms.is_empty() {
+            if target != 0 {
+                return None;
+            }
+            continue;
+        }
         let sum = Int::add(&terms.iter().collect::<Vec<&Int>>());
-        opt.assert(&sum.eq(Int::from_u64(target as u64)));
+        opt.assert(&sum.eq(Int::from_u64(target)));
     }
 
     opt.assert(&total_presses.eq(Int::add(&button_presses)));
@@ -69,97 +123,291 @@ fn fewest_joltages_min(machine: &Machine) -> u64 {
 
     match opt.check(&[]) {
         SatResult::Sat => opt
-            .get_model()
-            .unwrap()
+            .get_model()?
             .eval(&total_presses, true)
-            .and_then(|t| t.as_u64())
-            .unwrap(),
-        _ => panic!("No solution found"),
+            .and_then(|t| t.as_u64()),
+        _ => None,
     }
 }
 
-fn fewest_presses(machine: &Machine) -> u64 {
-    let seen: HashSet<u64> = HashSet::new();
-
-    let mut queue: VecDeque<(u64, u64)> = VecDeque::new();
-
-    machine.buttons.iter().for_each(|btn| {
-        queue.push_back((1, *btn));
-    });
-
-    while !queue.is_empty() {
-        let (count, val) = queue.pop_front().unwrap();
+fn fewest_presses(machine: &Machine) -> Option<u64> {
+    let mut seen: HashSet<u64> = HashSet::from([0]);
+    let mut queue: VecDeque<(u64, u64)> = VecDeque::from([(0, 0)]);
 
+    while let Some((count, val)) = queue.pop_front() {
         if val == machine.target {
-            return count;
+            return Some(count);
         }
         machine.buttons.iter().for_each(|btn| {
             let new = val ^ btn;
-            if !seen.contains(&new) {
+            if seen.insert(new) {
                 queue.push_back((count + 1, new));
             }
         });
     }
-    panic!("No solution found");
+    None
+}
+
+/// Pressing a button twice undoes it, so part 1 picks the subset of buttons
+/// whose lights XOR to the target: a linear system over GF(2). Elimination
+/// gives one solution plus the button subsets that change nothing, and the
+/// fewest presses is the smallest of their combinations.
+///
+/// Subsets are tracked as bitmasks, so machines with more than 64 buttons fall
+/// back to [`fewest_presses`].
+fn fewest_presses_elimination(machine: &Machine) -> Option<u64> {
+    if machine.buttons.len() > 64 {
+        return fewest_presses(machine);
+    }
+
+    // Each basis vector keeps the buttons it was made from.
+    let mut basis: Vec<(u64, u64)> = vec![];
+    let mut null_space: Vec<u64> = vec![];
+    let reduce = |basis: &[(u64, u64)], mut lights: u64, mut pressed: u64| {
+        for (vector, buttons) in basis {
+            if lights & (1 << vector.ilog2()) != 0 {
+                lights ^= vector;
+                pressed ^= buttons;
+            }
+        }
+        (lights, pressed)
+    };
+
+    for (idx, button) in machine.buttons.iter().enumerate() {
+        match reduce(&basis, *button, 1 << idx) {
+            (0, pressed) => null_space.push(pressed),
+            reduced => basis.push(reduced),
+        }
+    }
+
+    let (0, pressed) = reduce(&basis, machine.target, 0) else {
+        return None;
+    };
+    (0..1u64 << null_space.len())
+        .map(|subset| {
+            null_space
+                .iter()
+                .enumerate()
+                .filter(|(idx, _)| subset & (1 << idx) != 0)
+                .fold(pressed, |acc, (_, buttons)| acc ^ buttons)
+                .count_ones() as u64
+        })
+        .min()
 }
 
 struct Machine {
+    /// The input line the machine was read from, for error messages.
+    line: usize,
     target: u64,
     buttons: Vec<u64>,
-    joltages: Vec<i64>,
+    joltages: Vec<u64>,
 }
 
-impl From<&str> for Machine {
-    fn from(value: &str) -> Self {
-        let info: Vec<&str> = value.split(" ").collect();
+impl TryFrom<Token<'_>> for Machine {
+    type Error = ParseError;
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        let info: Vec<Token> = value.split_whitespace().collect();
 
-        Self {
-            target: parse_target(info[0]),
-            joltages: parse_joltages(info[info.len() - 1]),
-            buttons: parse_buttons(&info[1..info.len() - 1]),
+        match &info[..] {
+            [target, buttons @ .., joltages] => {
+                let (target, lights) = parse_target(*target)?;
+                let joltages = parse_joltages(*joltages)?;
+                if joltages.len() != lights {
+                    return Err(value.error(format!("expected {lights} joltages")));
+                }
+                Ok(Self {
+                    line: value.line(),
+                    target,
+                    joltages,
+                    buttons: parse_buttons(buttons, lights)?,
+                })
+            }
+            _ => Err(value.error("expected a target, buttons and joltages")),
         }
     }
 }
 
-fn parse_target(target: &str) -> u64 {
-    let chars = target.strip_prefix("[").unwrap().strip_suffix("]").unwrap();
-    chars.chars().rev().fold(0, |acc, c| match c {
-        '#' => (acc << 1) | 1,
-        '.' => acc << 1,
-        _ => panic!(),
-    })
+/// The target as a bitmask, and the number of lights.
+fn parse_target(target: Token) -> Result<(u64, usize), ParseError> {
+    let chars = target.delimited('[', ']')?;
+    let lights = chars.text().chars().count();
+    if lights > 64 {
+        return Err(chars.error("a machine has at most 64 lights"));
+    }
+    let mask = chars.chars().rev().try_fold(0, |acc, (token, c)| match c {
+        '#' => Ok((acc << 1) | 1),
+        '.' => Ok(acc << 1),
+        _ => Err(token.error(format!("expected `#` or `.`, found `{c}`"))),
+    })?;
+    Ok((mask, lights))
+}
+
+fn parse_joltages(jolt: Token) -> Result<Vec<u64>, ParseError> {
+    jolt.delimited('{', '}')?.numbers(",")
 }
 
-fn parse_joltages(jolt: &str) -> Vec<i64> {
-    let n = jolt.strip_prefix("{").unwrap().strip_suffix("}").unwrap();
-    n.split(",").map(|num| num.parse().unwrap()).collect()
+fn parse_buttons(buttons: &[Token], lights: usize) -> Result<Vec<u64>, ParseError> {
+    buttons.iter().map(|b| parse_button(*b, lights)).collect()
 }
 
-fn parse_buttons(buttons: &[&str]) -> Vec<u64> {
-    buttons.iter().map(|b| parse_button(b)).collect()
+fn parse_button(btn: Token, lights: usize) -> Result<u64, ParseError> {
+    let n = btn.delimited('(', ')')?;
+
+    n.split(",").try_fold(0, |acc, num| {
+        let idx: usize = num.parse()?;
+        if idx >= lights {
+            return Err(num.error(format!("the machine only has {lights} lights")));
+        }
+        Ok(acc | (1 << idx))
+    })
 }
 
-fn parse_button(btn: &str) -> u64 {
-    let n = btn.strip_prefix("(").unwrap().strip_suffix(")").unwrap();
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+
+    pub struct Parsed(Vec<Machine>);
 
-    n.split(",")
-        .map(|num| num.parse::<u64>().unwrap())
-        .fold(0, |acc, num| acc | (1 << num))
+    /// `size` machines of four to ten lights, with a target and joltages
+    /// that some presses of their buttons reach.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(10);
+        let list = |values: &[u64]| {
+            values
+                .iter()
+                .map(u64::to_string)
+                .collect::<Vec<_>>()
+                .join(",")
+        };
+        (0..size)
+            .map(|_| {
+                let lights = rng.range(4, 11);
+                let buttons: Vec<Vec<u64>> = (0..rng.range(3, 11))
+                    .map(|_| {
+                        let wires: Vec<u64> = (0..lights).filter(|_| rng.chance(40)).collect();
+                        match wires.is_empty() {
+                            true => vec![rng.range(0, lights)],
+                            false => wires,
+                        }
+                    })
+                    .collect();
+
+                let mut target = vec!['.'; lights as usize];
+                let mut joltages = vec![0; lights as usize];
+                for wires in &buttons {
+                    let presses = rng.range(0, 20);
+                    for &wire in wires {
+                        if presses % 2 == 1 {
+                            let light = &mut target[wire as usize];
+                            *light = if *light == '#' { '.' } else { '#' };
+                        }
+                        joltages[wire as usize] += presses;
+                    }
+                }
+
+                let buttons: Vec<String> = buttons
+                    .iter()
+                    .map(|wires| format!("({})", list(wires)))
+                    .collect();
+                format!(
+                    "[{}] {} {{{}}}",
+                    target.iter().collect::<String>(),
+                    buttons.join(" "),
+                    list(&joltages)
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> Result<u64, solution::Error> {
+        super::part1(&parsed.0, super::fewest_presses)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<u64, solution::Error> {
+        super::part2(&parsed.0)
+    }
+
+    pub fn fewest_presses(parsed: &Parsed) -> Vec<Option<u64>> {
+        parsed.0.iter().map(super::fewest_presses).collect()
+    }
 }
 
 #[cfg(test)]
 mod tests {
 
-    use super::solution::Solver;
-    use crate::solutions::day10::Day10Solver;
-    #[test]
-    fn test_input() {
-        let input = r###"[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
+    use super::solution::{Solver, assert_line_ending_variants};
+    use crate::solutions::day10::{Day10EliminationSolver, Day10Solver};
+
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r###"[.##.] (3) (1,3) (2) (2,3) (0,2) (0,1) {3,5,4,7}
 [...#.] (0,2,3,4) (2,3) (0,4) (0,1,2) (1,2,3,4) {7,5,12,7,2}
 [.###.#] (0,1,2,3,4) (0,3,4) (0,1,2,4,5) (1,2) {10,11,11,5,10,5}"###;
 
-        let solution = Day10Solver.solve(input);
+    #[test]
+    fn test_input() {
+        let solution = Day10Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "7");
         assert_eq!(solution.part2, "33")
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day10Solver, INPUT);
+    }
+
+    #[test]
+    fn test_invalid_machines() {
+        let error = |input| Day10Solver.solve(input).unwrap_err().to_string();
+        assert_eq!(
+            error("[.#] (2) {1,1}"),
+            "invalid input at line 1, column 7: the machine only has 2 lights"
+        );
+        assert_eq!(
+            error("[.#] (0) {1}"),
+            "invalid input at line 1, column 1: expected 2 joltages"
+        );
+        assert_eq!(
+            error("[.#] (0) {0,0}"),
+            "no solution: no button presses reach the target of machine 1"
+        );
+    }
+
+    #[cfg(feature = "bench")]
+    #[test]
+    fn test_bench_input() {
+        let input = super::bench::input(20);
+        assert!(Day10Solver.solve(&input).is_ok(), "{input}");
+    }
+
+    #[test]
+    fn test_elimination_solver() {
+        assert_eq!(
+            Day10EliminationSolver.solve(INPUT),
+            Day10Solver.solve(INPUT)
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_elimination_matches_search(
+            target in 0..256u64,
+            buttons in prop::collection::vec(1..256u64, 0..10),
+        ) {
+            let machine = Machine { line: 1, target, buttons, joltages: vec![] };
+            prop_assert_eq!(fewest_presses_elimination(&machine), fewest_presses(&machine));
+        }
+    }
 }
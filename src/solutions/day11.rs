@@ -1,89 +1,139 @@
+use crate::parsing::{self, ParseError};
 use crate::solutions::solution;
+use crate::trace::Trace;
 
 pub struct Day11Solver;
 
 impl solution::Solver for Day11Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
-        }
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let graph = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&graph)?.to_string(),
+            part2: part2(&graph)?.to_string(),
+        })
     }
 }
 
-use std::collections::{HashMap, HashSet};
-type Nodes = HashMap<u32, HashSet<u32>>;
-
-fn paths(nodes: &Nodes, in_node: u32, out_node: u32, cache: &mut HashMap<u32, u64>) -> u64 {
-    if in_node == out_node {
-        return 1;
-    }
-    match cache.get(&in_node) {
-        Some(val) => *val,
-        None => {
-            let total = nodes
-                .get(&in_node)
-                .unwrap_or(&HashSet::new())
-                .iter()
-                .fold(0, |acc, node| acc + paths(nodes, *node, out_node, cache));
-            cache.insert(in_node, total);
-            total
-        }
+use crate::answer::Count;
+use crate::graph::Graph;
+
+/// The number of paths from `from` to `to`, which is only finite if no
+/// cycle can be reached from `from`; cycles elsewhere don't matter.
+fn paths(graph: &Graph, from: &str, to: &str) -> Result<Count, solution::Error> {
+    let (Some(from), Some(to)) = (graph.id(from), graph.id(to)) else {
+        return Ok(Count::zero());
+    };
+    let order = graph.topological_sort_from(from).map_err(|cycle| {
+        solution::Error::NoSolution(format!(
+            "device `{}` is part of a cycle reachable from `{}`",
+            graph.name(cycle[0]),
+            graph.name(from)
+        ))
+    })?;
+
+    let mut counts = vec![Count::zero(); graph.len()];
+    counts[to] = Count::one();
+
+    for node in order.into_iter().rev().filter(|n| *n != to) {
+        counts[node] = Count::sum(graph.successors(node).iter().map(|n| &counts[*n]))?;
     }
+    Ok(counts.swap_remove(from))
 }
 
-fn part2(input: &str) -> u64 {
-    let nodes = parse(input);
-
-    let svr = to_number("svr");
-    let fft = to_number("fft");
-    let dac = to_number("dac");
-    let out = to_number("out");
-
-    let p1 = paths(&nodes, svr, fft, &mut HashMap::new())
-        * paths(&nodes, fft, dac, &mut HashMap::new())
-        * paths(&nodes, dac, out, &mut HashMap::new());
+fn segments(graph: &Graph, route: &[&str]) -> Result<Count, solution::Error> {
+    let counts = route
+        .windows(2)
+        .map(|pair| paths(graph, pair[0], pair[1]))
+        .collect::<Result<Vec<Count>, solution::Error>>()?;
+    Ok(Count::product(&counts)?)
+}
 
-    let p2 = paths(&nodes, svr, dac, &mut HashMap::new())
-        * paths(&nodes, dac, fft, &mut HashMap::new())
-        * paths(&nodes, fft, out, &mut HashMap::new());
+fn part2(graph: &Graph) -> Result<Count, solution::Error> {
+    let p1 = segments(graph, &["svr", "fft", "dac", "out"])?;
+    let p2 = segments(graph, &["svr", "dac", "fft", "out"])?;
 
-    p1 + p2
+    Ok(p1.checked_add(&p2)?)
 }
 
-fn part1(input: &str) -> u64 {
-    let nodes = parse(input);
-    let (you, out) = (to_number("you"), to_number("out"));
-    paths(&nodes, you, out, &mut HashMap::new())
+fn part1(graph: &Graph) -> Result<Count, solution::Error> {
+    paths(graph, "you", "out")
 }
 
-fn parse(input: &str) -> Nodes {
-    input
-        .lines()
-        .map(|line| {
-            let nodes: Vec<&str> = line.split(": ").collect();
-            let in_node = to_number(nodes[0]);
-            let out_nodes: HashSet<u32> = nodes[1].split(" ").map(to_number).collect();
+fn parse(input: &str) -> Result<Graph, ParseError> {
+    let mut graph = Graph::new();
 
-            (in_node, out_nodes)
-        })
-        .collect()
+    for line in parsing::lines(input) {
+        let (in_node, out_nodes) = line.split_once(": ")?;
+        graph.intern(in_node.text());
+        for out_node in out_nodes.split_whitespace() {
+            graph.add_edge(in_node.text(), out_node.text());
+        }
+    }
+    Ok(graph)
 }
 
-fn to_number(node: &str) -> u32 {
-    assert_eq!(node.len(), 3);
-    node.char_indices()
-        .fold(0, |acc, (idx, c)| acc + ((c as u32) << (idx * 8)))
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+
+    pub struct Parsed(Graph);
+
+    /// `size` devices, each wired to one or two devices further along, from
+    /// `svr` and `you` at the start through `fft` and `dac` to `out` at the end.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(11);
+        let name = |idx: usize| match idx {
+            0 => "svr".to_string(),
+            1 => "you".to_string(),
+            _ if idx == size / 3 => "fft".to_string(),
+            _ if idx == 2 * size / 3 => "dac".to_string(),
+            _ if idx == size - 1 => "out".to_string(),
+            _ => format!("d{idx}"),
+        };
+        (0..size - 1)
+            .map(|idx| {
+                let mut outputs: Vec<usize> = (0..rng.range(1, 3))
+                    .map(|_| (idx + rng.range(1, 31) as usize).min(size - 1))
+                    .collect();
+                outputs.sort();
+                outputs.dedup();
+                let outputs: Vec<String> = outputs.into_iter().map(name).collect();
+                format!("{}: {}", name(idx), outputs.join(" "))
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> Result<Count, solution::Error> {
+        super::part1(&parsed.0)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, solution::Error> {
+        super::part2(&parsed.0)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Solver, assert_line_ending_variants};
     use crate::solutions::day11::Day11Solver;
 
-    #[test]
-    fn test_input_1() {
-        let input = r#"aaa: you hhh
+    const INPUT_1: &str = r#"aaa: you hhh
 you: bbb ccc
 bbb: ddd eee
 ccc: ddd eee fff
@@ -93,14 +143,7 @@ fff: out
 ggg: out
 hhh: ccc fff iii
 iii: out"#;
-
-        let solution = Day11Solver.solve(input);
-        assert_eq!(solution.part1, "5");
-    }
-
-    #[test]
-    fn test_input_2() {
-        let input = r#"svr: aaa bbb
+    const INPUT_2: &str = r#"svr: aaa bbb
 aaa: fft
 fft: ccc
 bbb: tty
@@ -113,7 +156,47 @@ dac: fff
 fff: ggg hhh
 ggg: out
 hhh: out"#;
-        let solution = Day11Solver.solve(input);
+    const LONG_NAMES: &str = r#"you: left right_branch
+left: out
+right_branch: middle out
+middle: out"#;
+
+    #[test]
+    fn test_input_1() {
+        let solution = Day11Solver.solve(INPUT_1).unwrap();
+        assert_eq!(solution.part1, "5");
+    }
+
+    #[test]
+    fn test_input_2() {
+        let solution = Day11Solver.solve(INPUT_2).unwrap();
         assert_eq!(solution.part2, "2");
     }
+
+    #[test]
+    fn test_long_names() {
+        let solution = Day11Solver.solve(LONG_NAMES).unwrap();
+        assert_eq!(solution.part1, "3");
+    }
+
+    #[test]
+    fn test_cycles() {
+        // `loop` and `back` go round in circles, but can't be reached from `you`.
+        let unreachable = format!("{LONG_NAMES}\nloop: back\nback: loop out");
+        let solution = Day11Solver.solve(&unreachable).unwrap();
+        assert_eq!(solution.part1, "3");
+
+        let reachable = format!("{LONG_NAMES}\nmiddle: loop\nloop: back\nback: loop");
+        assert_eq!(
+            Day11Solver.solve(&reachable).unwrap_err().to_string(),
+            "no solution: device `loop` is part of a cycle reachable from `you`"
+        );
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day11Solver, INPUT_1);
+        assert_line_ending_variants(&Day11Solver, INPUT_2);
+        assert_line_ending_variants(&Day11Solver, LONG_NAMES);
+    }
 }
@@ -1,17 +1,44 @@
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
-use regex::Regex;
+use crate::trace::Trace;
+use crate::viz::Frame;
 
 pub struct Day12Solver;
 
 impl solution::Solver for Day12Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        _trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let (shapes, grids) = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&shapes, &grids).to_string(),
             part2: "".into(),
-        }
+        })
+    }
+
+    fn visualize(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+    ) -> Result<Vec<Frame>, solution::Error> {
+        let (shapes, grids) = parse(input)?;
+        Ok(grids
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, grid)| {
+                let layout = grid.layout(&shapes)?;
+                Some(Frame::text(format!("region-{idx}"), layout))
+            })
+            .collect())
     }
 }
 
+/// The most cells a region can have and still be drawn.
+const MAX_DRAWN_CELLS: u64 = 1 << 20;
+
 type Coord = (u32, u32);
 
 #[derive(Debug)]
@@ -26,20 +53,27 @@ struct Grid {
     requirements: Vec<u32>,
 }
 
-impl From<&str> for Shape {
-    fn from(value: &str) -> Self {
-        let points = value
-            .lines()
-            .skip(1)
+impl TryFrom<&[Token<'_>]> for Shape {
+    type Error = ParseError;
+    fn try_from(value: &[Token]) -> Result<Self, Self::Error> {
+        let cells = parsing::grid(value.iter().skip(1).copied(), |c| match c {
+            '#' => Some(true),
+            '.' => Some(false),
+            _ => None,
+        })?;
+
+        let points = cells
+            .iter()
             .enumerate()
             .flat_map(|(row, l)| {
-                l.char_indices()
-                    .filter(|(_col, c)| *c == '#')
-                    .map(move |(col, _c)| (row as u32, col as u32))
+                l.iter()
+                    .enumerate()
+                    .filter(|(_col, filled)| **filled)
+                    .map(move |(col, _filled)| (row as u32, col as u32))
             })
             .collect();
 
-        Self { points }
+        Ok(Self { points })
     }
 }
 
@@ -47,30 +81,37 @@ impl Shape {
     fn area(&self) -> u32 {
         self.points.len() as u32
     }
+
+    /// Rows and columns of the shape's bounding box.
+    fn size(&self) -> (u32, u32) {
+        self.points.iter().fold((0, 0), |(rows, cols), (r, c)| {
+            (rows.max(r + 1), cols.max(c + 1))
+        })
+    }
 }
 
-impl From<&str> for Grid {
-    fn from(value: &str) -> Self {
-        let re = Regex::new(r"(?x)(?P<rows>\d+)x(?P<cols>\d+):\s(?P<reqs>[\d\s]+)").unwrap();
+impl TryFrom<Token<'_>> for Grid {
+    type Error = ParseError;
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        let (size, reqs) = value.split_once(":")?;
+        let (rows, cols) = size.split_once("x")?;
 
-        let caps = re.captures(value).unwrap();
+        let requirements = reqs
+            .split_whitespace()
+            .map(Token::parse)
+            .collect::<Result<_, _>>()?;
 
-        let reqs: Vec<u32> = caps["reqs"]
-            .split(" ")
-            .map(|n| n.parse().unwrap())
-            .collect();
-
-        Self {
-            rows: caps["rows"].parse().unwrap(),
-            cols: caps["cols"].parse().unwrap(),
-            requirements: reqs,
-        }
+        Ok(Self {
+            rows: rows.parse()?,
+            cols: cols.parse()?,
+            requirements,
+        })
     }
 }
 
 impl Grid {
-    fn area(&self) -> u32 {
-        self.cols * self.rows
+    fn area(&self) -> u128 {
+        self.cols as u128 * self.rows as u128
     }
 
     pub fn can_fit(&self, shapes: &[Shape]) -> bool {
@@ -78,25 +119,196 @@ impl Grid {
             .requirements
             .iter()
             .enumerate()
-            .map(|(idx, tot)| shapes[idx].area() * tot)
+            .map(|(idx, tot)| shapes[idx].area() as u128 * *tot as u128)
             .sum();
 
         self.area().gt(&total_area)
     }
+
+    /// Draws the required shapes packed shelf by shelf, without rotating or
+    /// interlocking them, each labelled with a letter for its index, or
+    /// `None` if the region has too many cells to draw.
+    fn layout(&self, shapes: &[Shape]) -> Option<String> {
+        if u64::from(self.rows) * u64::from(self.cols) > MAX_DRAWN_CELLS {
+            return None;
+        }
+        let mut canvas = vec![vec!['.'; self.cols as usize]; self.rows as usize];
+        let (mut row, mut col, mut shelf) = (0, 0, 0);
+        let (mut placed, mut missing) = (0, 0);
+
+        for (idx, count) in self.requirements.iter().enumerate() {
+            let (height, width) = shapes[idx].size();
+            for _ in 0..*count {
+                if col + width > self.cols {
+                    (row, col, shelf) = (row + shelf, 0, 0);
+                }
+                if row + height > self.rows || width > self.cols {
+                    missing += 1;
+                    continue;
+                }
+                let label = (b'A' + (idx % 26) as u8) as char;
+                for (r, c) in &shapes[idx].points {
+                    canvas[(row + r) as usize][(col + c) as usize] = label;
+                }
+                col += width;
+                shelf = shelf.max(height);
+                placed += 1;
+            }
+        }
+
+        let mut text = format!(
+            "{}x{}: fits by area: {}, placed {placed}, left over {missing}\n",
+            self.rows,
+            self.cols,
+            self.can_fit(shapes)
+        );
+        for line in canvas {
+            text.extend(line);
+            text.push('\n');
+        }
+        Some(text)
+    }
 }
 
-fn part1(input: &str) -> u32 {
-    let values: Vec<&str> = input.split("\n\n").collect();
+fn part1(shapes: &[Shape], grids: &[Grid]) -> u32 {
+    grids.iter().filter(|g| g.can_fit(shapes)).count() as u32
+}
+
+fn parse(input: &str) -> Result<(Vec<Shape>, Vec<Grid>), ParseError> {
+    let sections = parsing::sections(input);
+    let Some((regions, shapes)) = sections.split_last() else {
+        return Err(ParseError::end_of_input(
+            input,
+            "expected shapes and regions",
+        ));
+    };
+
+    let shapes: Vec<Shape> = shapes
+        .iter()
+        .map(|s| Shape::try_from(&s[..]))
+        .collect::<Result<_, _>>()?;
 
-    let shapes: Vec<Shape> = values[..values.len() - 1]
+    let grids = regions
         .iter()
-        .map(|s| Shape::from(*s))
-        .collect();
+        .map(|line| {
+            let grid = Grid::try_from(*line)?;
+            if grid.requirements.len() > shapes.len() {
+                return Err(line.error(format!("only {} shapes are defined", shapes.len())));
+            }
+            Ok(grid)
+        })
+        .collect::<Result<_, _>>()?;
+
+    Ok((shapes, grids))
+}
+
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
 
-    let grids: Vec<Grid> = values[values.len() - 1]
-        .split("\n")
-        .map(Grid::from)
-        .collect();
+    pub struct Parsed(Vec<Shape>, Vec<Grid>);
 
-    grids.iter().filter(|g| g.can_fit(&shapes)).count() as u32
+    /// Six three by three presents and `size` regions of up to fifty by fifty.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(12);
+        let mut sections: Vec<String> = (0..6)
+            .map(|idx| {
+                let rows: Vec<String> = (0..3)
+                    .map(|_| {
+                        (0..3)
+                            .map(|_| if rng.chance(70) { '#' } else { '.' })
+                            .collect()
+                    })
+                    .collect();
+                format!("{idx}:\n{}", rows.join("\n"))
+            })
+            .collect();
+        let regions: Vec<String> = (0..size)
+            .map(|_| {
+                let (cols, rows) = (rng.range(5, 51), rng.range(5, 51));
+                let counts: Vec<String> = (0..6)
+                    .map(|_| rng.range(0, cols * rows / 54 + 2).to_string())
+                    .collect();
+                format!("{cols}x{rows}: {}", counts.join(" "))
+            })
+            .collect();
+        sections.push(regions.join("\n"));
+        sections.join("\n\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        let (shapes, grids) = super::parse(input)?;
+        Ok(Parsed(shapes, grids))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> u32 {
+        super::part1(&parsed.0, &parsed.1)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::solution::{Params, Solver, assert_line_ending_variants};
+    use crate::solutions::day12::Day12Solver;
+
+    const INPUT: &str = r#"0:
+##
+#.
+
+1:
+###
+
+2x2: 1 0
+2x2: 1 1
+3x3: 1 1"#;
+
+    #[test]
+    fn test_input() {
+        let solution = Day12Solver.solve(INPUT).unwrap();
+        assert_eq!(solution.part1, "2");
+    }
+
+    #[test]
+    fn test_visualize() {
+        let frames = Day12Solver.visualize(INPUT, &Params::new()).unwrap();
+        let layouts: Vec<String> = frames
+            .iter()
+            .map(|f| String::from_utf8(f.bytes()).unwrap())
+            .collect();
+        assert_eq!(
+            layouts[0],
+            "2x2: fits by area: true, placed 1, left over 0\nAA\nA.\n"
+        );
+        assert_eq!(
+            layouts[2],
+            "3x3: fits by area: true, placed 2, left over 0\nAA.\nA..\nBBB\n"
+        );
+    }
+
+    #[test]
+    fn test_visualize_skips_huge_regions() {
+        let input = INPUT.replace("2x2: 1 1", "100000x100000: 1 1");
+        let frames = Day12Solver.visualize(&input, &Params::new()).unwrap();
+        let names: Vec<&str> = frames.iter().map(|f| f.name.as_str()).collect();
+        assert_eq!(names, ["region-0", "region-2"]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day12Solver, INPUT);
+    }
+
+    #[test]
+    fn test_huge_region() {
+        let solution = Day12Solver
+            .solve("0:\n#\n\n4294967295x4294967295: 4294967295")
+            .unwrap();
+        assert_eq!(solution.part1, "1");
+    }
 }
@@ -1,105 +1,764 @@
+use crate::answer::{Count, Overflow};
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
-use std::{cmp, collections::HashSet};
+use crate::trace::Trace;
+use std::cmp::{self, Reverse};
+use std::collections::BinaryHeap;
+use std::fmt;
+use std::ops::RangeInclusive;
 
 struct IdRange {
     low: String,
     high: String,
+    base: u32,
+}
+
+impl IdRange {
+    fn bounds(&self) -> RangeInclusive<u128> {
+        let value = |digits: &str| u128::from_str_radix(digits, self.base).unwrap();
+        value(&self.low)..=value(&self.high)
+    }
+}
+
+impl fmt::Display for IdRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}-{}", self.low, self.high)
+    }
 }
 pub struct Day2Solver;
 
+/// Sums each part by listing every invalid id, as a check on [`Day2Solver`]'s
+/// closed forms.
+pub struct Day2ListingSolver;
+
 impl solution::Solver for Day2Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input),
-            part2: part2(input),
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let ranges = parse(input, base(params)?)?;
+        Ok(solution::Solution {
+            part1: part1(&ranges, trace)?.to_string(),
+            part2: part2(&ranges, trace)?.to_string(),
+        })
+    }
+}
+
+impl solution::Solver for Day2ListingSolver {
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let ranges = parse(input, base(params)?)?;
+        Ok(solution::Solution {
+            part1: part1_by_listing(&ranges, trace)?.to_string(),
+            part2: part2_by_listing(&ranges, trace)?.to_string(),
+        })
+    }
+}
+
+/// The base the ids are written in, from the `base` param, 10 by default.
+fn base(params: &solution::Params) -> Result<u32, solution::Error> {
+    match params.get("base", 10)? {
+        base @ 2..=36 => Ok(base),
+        base => Err(solution::Error::Param(format!(
+            "base {base} is not from 2 to 36"
+        ))),
+    }
+}
+
+fn part1(ranges: &[IdRange], trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    sum_ranges(ranges, &Repetitions::Exactly(2), "part1", trace)
+}
+
+fn part2(ranges: &[IdRange], trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    sum_ranges(ranges, &Repetitions::AtLeast(2), "part2", trace)
+}
+
+/// Sums each range in closed form. Only when tracing are the invalid ids
+/// listed, one by one as the listing solver finds them, so `--explain` takes
+/// as long as listing.
+fn sum_ranges(
+    ranges: &[IdRange],
+    reps: &Repetitions,
+    part: &str,
+    trace: &mut dyn Trace,
+) -> Result<Count, Overflow> {
+    let mut total = Count::zero();
+    for range in ranges {
+        if trace.enabled() {
+            for id in invalid_ids(range.bounds(), reps, range.base) {
+                let digits = to_digits(id, range.base);
+                trace.step(
+                    &format!("{part} invalid id"),
+                    format_args!("{digits} in {range}"),
+                );
+            }
         }
+        let sum = sum_invalid(range.bounds(), reps, range.base)?;
+        trace.step(&format!("{part} range"), format_args!("{range}: {sum}"));
+        total = total.checked_add(&sum)?;
     }
+    Ok(total)
 }
 
-fn part2(input: &str) -> String {
-    input
-        .split(",")
-        .map(to_range)
-        .map(invalid_ids_sum_2)
-        .sum::<u64>()
-        .to_string()
+/// How many times a block of digits must be written out for an id to be
+/// invalid.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Repetitions {
+    /// Exactly this many times, part 1 with 2.
+    Exactly(u32),
+    /// This many times or more, part 2 with 2.
+    AtLeast(u32),
+    /// Any of these numbers of times.
+    AnyOf(Vec<u32>),
 }
 
-fn part1(input: &str) -> String {
-    let repetitions = 2;
-    input
-        .split(",")
-        .map(to_range)
-        .map(|range| invalid_ids(&range, repetitions).iter().sum::<u64>())
-        .sum::<u64>()
-        .to_string()
+impl Repetitions {
+    pub fn contains(&self, reps: u32) -> bool {
+        match self {
+            Repetitions::Exactly(n) => reps == *n,
+            Repetitions::AtLeast(n) => reps >= *n,
+            Repetitions::AnyOf(set) => set.contains(&reps),
+        }
+    }
+
+    /// The lengths of the blocks that make a `digits`-long id invalid.
+    fn blocks(&self, digits: u32) -> Vec<u32> {
+        (1..=digits)
+            .filter(|block| digits.is_multiple_of(*block) && self.contains(digits / block))
+            .collect()
+    }
 }
 
-fn to_range(range: &str) -> IdRange {
-    let r: Vec<&str> = range.split("-").take(2).collect();
+// The functions below take ids written in any `base` from 2 to 36, and panic
+// for others.
 
-    match r[..] {
-        [low, high] => IdRange {
-            low: low.to_string(),
-            high: high.to_string(),
-        },
-        _ => panic!("Unexpected vector length: {}", r.len()),
+/// Whether `id` is a block of digits written out between `min_reps` and
+/// `max_reps` times. Ids start at 1, so 0 never is.
+pub fn is_invalid(id: u128, min_reps: u32, max_reps: u32, base: u32) -> bool {
+    let digits = to_digits(id, base);
+    let len = digits.len() as u32;
+    id > 0
+        && (min_reps.max(1)..=max_reps.min(len)).any(|reps| {
+            len.is_multiple_of(reps)
+                && digits == digits[..(len / reps) as usize].repeat(reps as usize)
+        })
+}
+
+/// How many ids in `range` are invalid under `reps`, without listing them.
+pub fn count_invalid(range: RangeInclusive<u128>, reps: &Repetitions, base: u32) -> u128 {
+    // The count fits in a u128, so wrapping arithmetic gets it exactly even
+    // where the weighted terms on the way there don't.
+    weighted_blocks(&range, reps, base).fold(0, |total: u128, (digits, block, weight)| {
+        let count =
+            block_span(&range, digits, block, base).map_or(0, |(_, first, last)| last - first + 1);
+        let term = count.wrapping_mul(weight.unsigned_abs());
+        match weight < 0 {
+            true => total.wrapping_sub(term),
+            false => total.wrapping_add(term),
+        }
+    })
+}
+
+/// The sum of the ids in `range` that are invalid under `reps`, without
+/// listing them.
+pub fn sum_invalid(
+    range: RangeInclusive<u128>,
+    reps: &Repetitions,
+    base: u32,
+) -> Result<Count, Overflow> {
+    let (mut added, mut removed) = (Count::zero(), Count::zero());
+    for (digits, block, weight) in weighted_blocks(&range, reps, base) {
+        let sum = repeated_sum(&range, digits, block, base)?
+            .checked_mul(&Count::try_from(weight.unsigned_abs())?)?;
+        match weight < 0 {
+            true => removed = removed.checked_add(&sum)?,
+            false => added = added.checked_add(&sum)?,
+        }
     }
+    added.checked_sub(&removed)
+}
+
+/// The ids in `range` that are invalid under `reps`, in ascending order.
+pub fn invalid_ids(
+    range: RangeInclusive<u128>,
+    reps: &Repetitions,
+    base: u32,
+) -> impl Iterator<Item = u128> {
+    let reps = reps.clone();
+    digit_counts(&range, base).flat_map(move |digits| {
+        // The next id of each block length, with its block, factor and last
+        // block, smallest id first.
+        let mut next: BinaryHeap<Reverse<(u128, u128, u128, u128)>> = reps
+            .blocks(digits)
+            .into_iter()
+            .filter_map(|block| block_span(&range, digits, block, base))
+            .map(|(factor, first, last)| Reverse((first * factor, first, factor, last)))
+            .collect();
+        // An id made of several block lengths comes up once for each.
+        let mut previous = None;
+        std::iter::from_fn(move || {
+            loop {
+                let Reverse((id, block, factor, last)) = next.pop()?;
+                if block < last {
+                    next.push(Reverse((id + factor, block + 1, factor, last)));
+                }
+                if previous != Some(id) {
+                    previous = Some(id);
+                    return Some(id);
+                }
+            }
+        })
+    })
 }
 
-fn invalid_ids_sum_2(range: IdRange) -> u64 {
-    (2..=range.high.len())
-        .flat_map(|rep| invalid_ids(&range, rep))
-        .collect::<HashSet<u64>>()
+fn digit_counts(range: &RangeInclusive<u128>, base: u32) -> RangeInclusive<u32> {
+    assert!((2..=36).contains(&base), "base {base} is not from 2 to 36");
+    let digits = |n: u128| n.checked_ilog(u128::from(base)).map_or(1, |log| log + 1);
+    digits(*range.start())..=digits(*range.end())
+}
+
+/// `n` written in `base`, with lowercase letters for the digits past 9.
+fn to_digits(mut n: u128, base: u32) -> String {
+    let mut digits = vec![];
+    loop {
+        digits.push(char::from_digit((n % u128::from(base)) as u32, base).unwrap());
+        n /= u128::from(base);
+        if n == 0 {
+            return digits.iter().rev().collect();
+        }
+    }
+}
+
+/// Each id length in `range` with the block lengths and weights whose
+/// repeated numbers add up to the invalid ids of that length.
+///
+/// The ids repeating a block of length `b` are those repeating any divisor of
+/// `b`, so they overlap. Summing the sets for every divisor `d` of the length
+/// weighted by the Möbius function counts each invalid id exactly once:
+/// `d` weighs the sum of `μ(e / d)` over the multiples `e` of `d` that divide
+/// an allowed block length.
+fn weighted_blocks(
+    range: &RangeInclusive<u128>,
+    reps: &Repetitions,
+    base: u32,
+) -> impl Iterator<Item = (u32, u32, i128)> {
+    digit_counts(range, base).flat_map(move |digits| {
+        let blocks = reps.blocks(digits);
+        let divisors: Vec<u32> = (1..=digits).filter(|d| digits.is_multiple_of(*d)).collect();
+        divisors
+            .iter()
+            .map(|&divisor| {
+                let weight = divisors
+                    .iter()
+                    .filter(|e| {
+                        e.is_multiple_of(divisor) && blocks.iter().any(|b| b.is_multiple_of(**e))
+                    })
+                    .map(|e| mobius(e / divisor))
+                    .sum();
+                (digits, divisor, weight)
+            })
+            .filter(|(_, _, weight)| *weight != 0)
+            .collect::<Vec<_>>()
+    })
+}
+
+/// For the `digits`-long ids in `range` made of a `block`-digit number written
+/// out `digits / block` times: the factor `1 + base^block + base^(2 block) + ...`
+/// that turns a block into its id, and the first and last such block, if any.
+fn block_span(
+    range: &RangeInclusive<u128>,
+    digits: u32,
+    block: u32,
+    base: u32,
+) -> Option<(u128, u128, u128)> {
+    let pow = |exp: u32| u128::from(base).checked_pow(exp);
+    // Past a u128 there are no ids to find.
+    let below = |exp: u32| pow(exp).map_or(u128::MAX, |pow| pow - 1);
+    let factor =
+        (0..digits / block).try_fold(0u128, |sum, rep| sum.checked_add(pow(rep * block)?))?;
+    let low = (*range.start()).max(pow(digits - 1)?);
+    let high = (*range.end()).min(below(digits));
+
+    let first = low.div_ceil(factor).max(pow(block - 1)?);
+    let last = (high / factor).min(below(block));
+    (first <= last).then_some((factor, first, last))
+}
+
+/// The sum of the ids [`block_span`] describes, an arithmetic series.
+fn repeated_sum(
+    range: &RangeInclusive<u128>,
+    digits: u32,
+    block: u32,
+    base: u32,
+) -> Result<Count, Overflow> {
+    let Some((factor, first, last)) = block_span(range, digits, block, base) else {
+        return Ok(Count::zero());
+    };
+    let count = last - first + 1;
+    // Halve whichever of `count` and `first + last` is even; the latter
+    // without adding them, which could overflow.
+    let series = match count % 2 {
+        0 => Count::try_from(count / 2)?
+            .checked_mul(&Count::try_from(first)?.checked_add(&Count::try_from(last)?)?)?,
+        _ => Count::try_from(count)?
+            .checked_mul(&Count::try_from(first / 2 + last / 2 + (first & 1))?)?,
+    };
+    series.checked_mul(&Count::try_from(factor)?)
+}
+
+/// The Möbius function: 0 if `n` has a square factor, otherwise -1 or 1 for
+/// an odd or even number of prime factors.
+fn mobius(mut n: u32) -> i128 {
+    let mut result = 1;
+    let mut prime = 2;
+    while prime * prime <= n {
+        if n.is_multiple_of(prime) {
+            n /= prime;
+            if n.is_multiple_of(prime) {
+                return 0;
+            }
+            result = -result;
+        }
+        prime += 1;
+    }
+    if n > 1 { -result } else { result }
+}
+
+fn part2_by_listing(ranges: &[IdRange], trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    let sums = ranges
         .iter()
-        .sum()
+        .map(|range| invalid_ids_sum_2(range, trace))
+        .collect::<Result<Vec<Count>, Overflow>>()?;
+    Count::sum(&sums)
+}
+
+fn part1_by_listing(ranges: &[IdRange], trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    let repetitions = 2;
+    let mut total = Count::zero();
+    for range in ranges {
+        for id in repeated_ids(range, repetitions) {
+            let digits = to_digits(id, range.base);
+            trace.step("part1 invalid id", format_args!("{digits} in {range}"));
+            total = total.checked_add(&Count::try_from(id)?)?;
+        }
+    }
+    Ok(total)
 }
 
-fn invalid_ids(range: &IdRange, repetitions: usize) -> Vec<u64> {
-    let high: u64 = range.high.parse().unwrap();
-    (first_invalid_id_after(&range.low, repetitions)..)
-        .map(|n| generate_invalid_id(n, repetitions))
-        .take_while(|n| *n <= high)
+fn parse(input: &str, base: u32) -> Result<Vec<IdRange>, ParseError> {
+    parsing::lines(input)
+        .flat_map(|line| line.split(","))
+        .filter(|range| !range.trim().is_empty())
+        .map(|range| to_range(range.trim(), base))
         .collect()
 }
 
-fn first_invalid_id_after(number: &str, reps: usize) -> u64 {
+fn to_range(range: Token, base: u32) -> Result<IdRange, ParseError> {
+    let (low, high) = range.split_once("-")?;
+
+    // Store the canonical digits, so `+7`, `007` or `A` in base 16 become `7`,
+    // `7` and `a`.
+    let canonical = |number: Token| {
+        u128::from_str_radix(number.text(), base)
+            .map(|n| to_digits(n, base))
+            .map_err(|e| {
+                number.error(format!(
+                    "cannot parse `{}` in base {base}: {e}",
+                    number.text()
+                ))
+            })
+    };
+    Ok(IdRange {
+        low: canonical(low)?,
+        high: canonical(high)?,
+        base,
+    })
+}
+
+fn invalid_ids_sum_2(range: &IdRange, trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    let mut total = Count::zero();
+    for rep in 2..=range.high.len() {
+        // An id made of a repeated block is also made of a repeated shorter
+        // block when the block itself repeats, so only count primitive blocks.
+        for id in repeated_ids(range, rep) {
+            let digits = to_digits(id, range.base);
+            if !is_repetition(&digits[..digits.len() / rep]) {
+                trace.step("part2 invalid id", format_args!("{digits} in {range}"));
+                total = total.checked_add(&Count::try_from(id)?)?;
+            }
+        }
+    }
+    Ok(total)
+}
+
+fn is_repetition(digits: &str) -> bool {
+    (1..digits.len()).any(|len| {
+        digits.len().is_multiple_of(len) && digits == digits[..len].repeat(digits.len() / len)
+    })
+}
+
+fn repeated_ids(range: &IdRange, repetitions: usize) -> impl Iterator<Item = u128> {
+    let (high, base) = (*range.bounds().end(), range.base);
+    (first_invalid_id_after(&range.low, repetitions, base)..)
+        .map_while(move |n| generate_invalid_id(n, repetitions, base))
+        .take_while(move |n| *n <= high)
+}
+
+fn first_invalid_id_after(number: &str, reps: usize, base: u32) -> u128 {
     let nl = number.len();
 
-    let guess: u64 = if nl.is_multiple_of(reps) {
-        number[..cmp::max(1, nl / reps)].parse().unwrap()
+    let guess: u128 = if nl.is_multiple_of(reps) {
+        u128::from_str_radix(&number[..cmp::max(1, nl / reps)], base).unwrap()
     } else {
-        10u64.pow((nl / reps) as u32)
+        u128::from(base).pow((nl / reps) as u32)
     };
 
-    let start: u64 = number.parse().unwrap();
+    let start = u128::from_str_radix(number, base).unwrap();
 
+    // Past u128 counts as past `start`; the caller stops there.
     (guess..)
-        .into_iter()
-        .find(|n| generate_invalid_id(*n, reps) >= start)
+        .find(|n| generate_invalid_id(*n, reps, base).is_none_or(|id| id >= start))
         .unwrap()
 }
 
-fn generate_invalid_id(num: u64, repetitions: usize) -> u64 {
-    let step = num.ilog10() + 1;
+/// `num` written out `repetitions` times in `base`, if that fits in a u128.
+fn generate_invalid_id(num: u128, repetitions: usize, base: u32) -> Option<u128> {
+    let base = u128::from(base);
+    let step = num.ilog(base) + 1;
+
+    (1..repetitions as u32).try_fold(num, |acc, rep| {
+        acc.checked_add(num.checked_mul(base.checked_pow(step * rep)?)?)
+    })
+}
+
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed(Vec<IdRange>);
+
+    /// `size` ranges of IDs with two to twelve digits.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(2);
+        (0..size)
+            .map(|_| {
+                let digits = rng.range(2, 13) as u32;
+                let low = rng.range(10u64.pow(digits - 1), 10u64.pow(digits));
+                let high = low + rng.range(0, low / 1000 + 10);
+                format!("{low}-{high}")
+            })
+            .collect::<Vec<_>>()
+            .join(",")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input, 10)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part1(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part2(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn part2_by_listing(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part2_by_listing(&parsed.0, &mut NoTrace)
+    }
 
-    (1_usize..repetitions)
-        .step_by(1)
-        .fold(num, |acc, rep| acc + num * 10u64.pow(step * rep as u32))
+    pub fn generate_invalid_id(num: u128, repetitions: usize) -> Option<u128> {
+        super::generate_invalid_id(num, repetitions, 10)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::solutions::day2::Day2Solver;
+    use crate::trace::{NoTrace, RecordTrace};
+
+    use super::solution::{Params, Solver, assert_line_ending_variants};
+    use super::*;
+    use proptest::prelude::*;
 
-    use super::solution::Solver;
+    const INPUT: &str = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
 
     #[test]
     fn test_solve() {
-        let input = "11-22,95-115,998-1012,1188511880-1188511890,222220-222224,1698522-1698528,446443-446449,38593856-38593862,565653-565659,824824821-824824827,2121212118-2121212124";
-
-        let solution = Day2Solver.solve(input);
+        let solution = Day2Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "1227775554");
         assert_eq!(solution.part2, "4174379265");
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day2Solver, INPUT);
+    }
+
+    #[test]
+    fn test_explain_lists_ids() {
+        let input = "11-22,95-115,998-1012";
+        let mut trace = RecordTrace::default();
+        Day2Solver
+            .solve_with(input, &Params::new(), &mut trace)
+            .unwrap();
+        assert_eq!(
+            trace.details("part1 invalid id"),
+            vec![
+                "11 in 11-22",
+                "22 in 11-22",
+                "99 in 95-115",
+                "1010 in 998-1012"
+            ]
+        );
+        assert_eq!(
+            trace.details("part2 invalid id"),
+            vec![
+                "11 in 11-22",
+                "22 in 11-22",
+                "99 in 95-115",
+                "111 in 95-115",
+                "999 in 998-1012",
+                "1010 in 998-1012"
+            ]
+        );
+        assert_eq!(trace.details("part1 range")[2], "998-1012: 1010");
+
+        // The listing solver reports the same ids.
+        let mut listing = RecordTrace::default();
+        Day2ListingSolver
+            .solve_with(input, &Params::new(), &mut listing)
+            .unwrap();
+        for label in ["part1 invalid id", "part2 invalid id"] {
+            let mut ids = listing.details(label);
+            ids.sort_by_key(|detail| detail.split_once(' ').unwrap().0.parse::<u64>().unwrap());
+            assert_eq!(ids, trace.details(label));
+        }
+    }
+
+    #[test]
+    fn test_listing_solver() {
+        assert_eq!(
+            Day2ListingSolver.solve(INPUT).unwrap(),
+            Day2Solver.solve(INPUT).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_mobius() {
+        let values: Vec<i128> = (1..=12).map(mobius).collect();
+        assert_eq!(values, vec![1, -1, -1, 0, -1, 1, -1, 0, 0, 1, -1, 0]);
+    }
+
+    #[test]
+    fn test_is_invalid() {
+        assert!(is_invalid(11, 2, 2, 10));
+        assert!(!is_invalid(111, 2, 2, 10));
+        assert!(is_invalid(111, 2, 3, 10));
+        assert!(is_invalid(121212, 3, 3, 10));
+        assert!(!is_invalid(121212, 2, 2, 10));
+        assert!(is_invalid(7, 1, 1, 10));
+        assert!(!is_invalid(0, 1, 5, 10));
+        // 0b1010 and 0x1212.
+        assert!(is_invalid(10, 2, 2, 2));
+        assert!(is_invalid(0x1212, 2, 2, 16));
+        assert!(!is_invalid(0x1212, 2, 2, 10));
+    }
+
+    #[test]
+    fn test_repetitions() {
+        let ids = |range, reps| invalid_ids(range, &reps, 10).collect::<Vec<_>>();
+        assert_eq!(ids(95..=115, Repetitions::Exactly(2)), vec![99]);
+        assert_eq!(ids(998..=1012, Repetitions::AtLeast(2)), vec![999, 1010]);
+        // 1111 is 11 twice as well as 1 four times, but is listed once.
+        assert_eq!(ids(1000..=1111, Repetitions::AtLeast(2)), vec![1010, 1111]);
+
+        let triples = Repetitions::AnyOf(vec![3]);
+        assert_eq!(ids(1..=1000, triples.clone()).len(), 9);
+        assert_eq!(count_invalid(1..=1000, &triples, 10), 9);
+        assert_eq!(sum_invalid(1..=1000, &triples, 10), Ok(Count::from(4995)));
+
+        let solution = Day2Solver.solve(INPUT).unwrap();
+        let sum = |reps| {
+            let sums: Vec<Count> = parse(INPUT, 10)
+                .unwrap()
+                .iter()
+                .map(|range| sum_invalid(range.bounds(), &reps, 10).unwrap())
+                .collect();
+            Count::sum(&sums).unwrap().to_string()
+        };
+        assert_eq!(sum(Repetitions::Exactly(2)), solution.part1);
+        assert_eq!(sum(Repetitions::AtLeast(2)), solution.part2);
+    }
+
+    #[test]
+    fn test_closed_form_on_wide_ranges() {
+        let ranges = parse("1-999999999999", 10).unwrap();
+        assert_eq!(
+            part1(&ranges, &mut NoTrace),
+            part1_by_listing(&ranges, &mut NoTrace)
+        );
+        assert_eq!(
+            part2(&ranges, &mut NoTrace),
+            part2_by_listing(&ranges, &mut NoTrace)
+        );
+
+        // Too many ids to list, and their sum outgrows u64.
+        let ranges = parse("1-18446744073709551615", 10).unwrap();
+        assert_eq!(
+            part2(&ranges, &mut NoTrace).is_ok(),
+            cfg!(feature = "bigint")
+        );
+    }
+
+    #[test]
+    fn test_bases() {
+        let solve = |input: &str, base: &str| {
+            let params = Params::from([("base", base)]);
+            Day2Solver.solve_with(input, &params, &mut NoTrace)
+        };
+        // 11, 1010 and 1111 repeat twice, and 111 and 1111 at least twice.
+        let solution = solve("1-1111", "2").unwrap();
+        assert_eq!(
+            (solution.part1.as_str(), solution.part2.as_str()),
+            ("28", "35")
+        );
+        assert_eq!(solve("zz-ZZ", "36").unwrap().part1, "1295");
+
+        assert_eq!(
+            solve("1-2", "37").unwrap_err().to_string(),
+            "invalid parameter: base 37 is not from 2 to 36"
+        );
+        assert_eq!(
+            solve("1-12", "2").unwrap_err().to_string(),
+            "invalid input at line 1, column 3: cannot parse `12` in base 2: invalid digit found in string"
+        );
+    }
+
+    #[test]
+    fn test_u128_ranges() {
+        let max = u128::MAX.to_string();
+        let ranges = parse(&format!("1-{max}"), 10).unwrap();
+        assert_eq!(
+            part1(&ranges, &mut NoTrace).is_ok(),
+            cfg!(feature = "bigint")
+        );
+
+        // A few hundred doubled ids of 38 decimal or 24 base 36 digits.
+        for (base, digits) in [(10, 38), (36, 24)] {
+            let middle = 5 * u128::pow(base.into(), digits - 1);
+            let range = IdRange {
+                low: to_digits(middle - (1 << 70), base),
+                high: to_digits(middle + (1 << 70), base),
+                base,
+            };
+            let listed: Vec<u128> = repeated_ids(&range, 2).collect();
+            let reps = Repetitions::Exactly(2);
+            assert!(!listed.is_empty());
+            assert_eq!(
+                invalid_ids(range.bounds(), &reps, base).collect::<Vec<_>>(),
+                listed
+            );
+            assert_eq!(
+                count_invalid(range.bounds(), &reps, base),
+                listed.len() as u128
+            );
+        }
+        assert_eq!(
+            count_invalid(0..=u128::MAX, &Repetitions::AtLeast(1), 2),
+            u128::MAX
+        );
+    }
+
+    /// Whether `id` is some digit sequence repeated `reps` times.
+    fn repeats(id: u64, reps: usize) -> bool {
+        let text = id.to_string();
+        text.len().is_multiple_of(reps) && text == text[..text.len() / reps].repeat(reps)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_print_round_trip(ranges in prop::collection::vec((any::<u64>(), any::<u64>()), 1..10)) {
+            let input = ranges
+                .iter()
+                .map(|(low, high)| format!("{low}-{high}"))
+                .collect::<Vec<_>>()
+                .join(",");
+            let printed = parse(&input, 10)
+                .unwrap()
+                .iter()
+                .map(IdRange::to_string)
+                .collect::<Vec<_>>()
+                .join(",");
+            prop_assert_eq!(printed, input);
+        }
+
+        #[test]
+        fn prop_matches_brute_force(low in 1..100_000u64, len in 0..5_000u64) {
+            let high = low + len;
+            let range = IdRange { low: low.to_string(), high: high.to_string(), base: 10 };
+            let twice: u64 = (low..=high).filter(|id| repeats(*id, 2)).sum();
+            let any: u64 = (low..=high)
+                .filter(|id| (2..=id.to_string().len()).any(|reps| repeats(*id, reps)))
+                .sum();
+            prop_assert_eq!(repeated_ids(&range, 2).sum::<u128>(), u128::from(twice));
+            prop_assert_eq!(invalid_ids_sum_2(&range, &mut NoTrace).unwrap(), Count::from(any));
+        }
+
+        #[test]
+        fn prop_api_matches_is_invalid(
+            low in 1..1_000_000u64,
+            len in 0..5_000u64,
+            policy in 0..3usize,
+            n in 1..8u32,
+            set in prop::collection::vec(1..8u32, 0..4),
+            base in 2..=36u32,
+        ) {
+            let reps = match policy {
+                0 => Repetitions::Exactly(n),
+                1 => Repetitions::AtLeast(n),
+                _ => Repetitions::AnyOf(set),
+            };
+            let range = u128::from(low)..=u128::from(low + len);
+            let expected: Vec<u128> = range
+                .clone()
+                .filter(|id| (1..=20).any(|r| reps.contains(r) && is_invalid(*id, r, r, base)))
+                .collect();
+            let sum = Count::try_from(expected.iter().sum::<u128>()).unwrap();
+            prop_assert_eq!(invalid_ids(range.clone(), &reps, base).collect::<Vec<_>>(), expected.clone());
+            prop_assert_eq!(count_invalid(range.clone(), &reps, base), expected.len() as u128);
+            prop_assert_eq!(sum_invalid(range, &reps, base).unwrap(), sum);
+        }
+
+        #[test]
+        fn prop_closed_form_matches_listing(
+            low in 1..10_000_000_000u128,
+            len in 0..100_000_000u128,
+            base in 2..=36u32,
+        ) {
+            let input = format!("{}-{}", to_digits(low, base), to_digits(low + len, base));
+            let ranges = parse(&input, base).unwrap();
+            prop_assert_eq!(
+                part1(&ranges, &mut NoTrace),
+                part1_by_listing(&ranges, &mut NoTrace)
+            );
+            prop_assert_eq!(
+                part2(&ranges, &mut NoTrace),
+                part2_by_listing(&ranges, &mut NoTrace)
+            );
+        }
+    }
 }
@@ -1,77 +1,499 @@
+use crate::answer::{Count, Overflow};
+use crate::parsing::{self, ParseError};
 use crate::solutions::solution;
+use crate::trace::Trace;
 
 pub struct Day3Solver;
 
 impl solution::Solver for Day3Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input),
-            part2: part2(input),
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let banks = parse(input)?;
+        let pick = pick(params)?;
+        let top = params.get("top", 0)?;
+        Ok(solution::Solution {
+            part1: total_joltage(&banks, 2, pick, top, trace)?.to_string(),
+            part2: total_joltage(&banks, 12, pick, top, trace)?.to_string(),
+        })
+    }
+}
+
+/// The joltage of the `top` best banks picked from with `pick`, or of every
+/// bank if `top` is 0.
+fn total_joltage(
+    banks: &[&str],
+    num_batteries: usize,
+    pick: Pick,
+    top: usize,
+    trace: &mut dyn Trace,
+) -> Result<Count, solution::Error> {
+    let selections = banks
+        .iter()
+        .enumerate()
+        .map(|(idx, bank)| {
+            pick.select(bank, num_batteries).ok_or_else(|| {
+                solution::Error::NoSolution(format!(
+                    "bank {} has no {num_batteries} batteries starting with a nonzero digit",
+                    idx + 1
+                ))
+            })
+        })
+        .collect::<Result<Vec<Selection>, _>>()?;
+    let chosen = match top {
+        0 => (0..banks.len()).collect(),
+        n => top_banks(&selections, n),
+    };
+
+    let joltages = chosen
+        .into_iter()
+        .map(|idx| {
+            let (bank, selection) = (banks[idx], &selections[idx]);
+            let joltage = selection.joltage()?;
+            trace.step(
+                "bank",
+                format_args!(
+                    "{bank}: chose {joltage} from {num_batteries} batteries at {:?}",
+                    selection.indices
+                ),
+            );
+            Ok(joltage)
+        })
+        .collect::<Result<Vec<Count>, Overflow>>()?;
+    Ok(Count::sum(&joltages)?)
+}
+
+/// How to pick a bank's batteries.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Pick {
+    /// The largest number keeping the bank's order, as the puzzle asks.
+    Max,
+    /// The smallest such number, which may start with a zero only if
+    /// `leading_zero` allows it.
+    Min { leading_zero: bool },
+    /// The largest run of neighbouring batteries.
+    Window,
+}
+
+impl Pick {
+    /// `None` only if the pick needs a nonzero first digit that isn't there.
+    pub fn select(self, bank: &str, k: usize) -> Option<Selection> {
+        match self {
+            Pick::Max => Some(select_max(bank, k)),
+            Pick::Min { leading_zero } => select_min(bank, k, leading_zero),
+            Pick::Window => Some(best_window(bank, k)),
         }
     }
 }
 
-fn part2(input: &str) -> String {
-    input
-        .lines()
-        .map(|bank| max_joltage(bank, 12))
-        .sum::<u64>()
-        .to_string()
+/// The pick from the `select` param, `max`, `min` or `window`, with the
+/// `leading_zero` param for `min`.
+fn pick(params: &solution::Params) -> Result<Pick, solution::Error> {
+    match params.get("select", "max".to_string())?.as_str() {
+        "max" => Ok(Pick::Max),
+        "min" => Ok(Pick::Min {
+            leading_zero: params.get("leading_zero", true)?,
+        }),
+        "window" => Ok(Pick::Window),
+        other => Err(solution::Error::Param(format!(
+            "`{other}` is not a selection, expected `max`, `min` or `window`"
+        ))),
+    }
+}
+
+fn parse(input: &str) -> Result<Vec<&str>, ParseError> {
+    parsing::lines(input)
+        .map(|bank| {
+            if let Some((token, c)) = bank.chars().find(|(_, c)| !c.is_ascii_digit()) {
+                return Err(token.error(format!("expected a digit, found `{c}`")));
+            }
+            if bank.text().len() < 12 {
+                return Err(bank.error("a bank needs at least 12 batteries"));
+            }
+            Ok(bank.text())
+        })
+        .collect()
 }
 
-fn part1(input: &str) -> String {
-    input
-        .lines()
-        .map(|bank| max_joltage(bank, 2))
-        .sum::<u64>()
-        .to_string()
+/// Batteries picked from a bank, in bank order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Selection {
+    /// The picked digits, as long as they need to be.
+    pub digits: String,
+    /// Where each digit sits in the bank.
+    pub indices: Vec<usize>,
 }
 
-fn max_joltage(bank: &str, num_batteries: u8) -> u64 {
-    match num_batteries {
-        0 => 0,
-        n => do_max_joltage(bank, n),
+impl Selection {
+    /// Orders selections by joltage, without building the numbers.
+    fn joltage_key(&self) -> (usize, &str) {
+        let digits = self.digits.trim_start_matches('0');
+        (digits.len(), digits)
+    }
+
+    /// The digits as a number.
+    pub fn joltage(&self) -> Result<Count, Overflow> {
+        let ten = Count::from(10);
+        self.digits
+            .bytes()
+            .try_fold(Count::zero(), |joltage, digit| {
+                joltage
+                    .checked_mul(&ten)?
+                    .checked_add(&Count::from(u64::from(digit - b'0')))
+            })
     }
 }
 
-fn do_max_joltage(bank: &str, num_batteries: u8) -> u64 {
-    let mut d = '0';
-    let mut idx = 0;
+/// The largest `k` digits of `bank` in order, or all of them if it has
+/// fewer, in one pass: each digit pops the smaller ones before it off a stack
+/// while enough digits remain to make up `k`.
+pub fn select_max(bank: &str, k: usize) -> Selection {
+    selection(bank, select_by_stack(bank, 0, k, |top, digit| top < digit))
+}
 
-    for (i, c) in bank[0..(bank.len() - (num_batteries as usize - 1))]
-        .chars()
-        .enumerate()
-    {
-        if c == '9' {
-            d = c;
-            idx = i;
-            break;
-        }
-        if c > d {
-            d = c;
-            idx = i;
+/// The smallest `k` digits of `bank` in order, or all of them if it has
+/// fewer. Unless `leading_zero`, the first is the smallest nonzero digit
+/// that leaves room for the rest, and `None` if there is none.
+pub fn select_min(bank: &str, k: usize, leading_zero: bool) -> Option<Selection> {
+    let larger = |top, digit| top > digit;
+    if leading_zero || k == 0 || bank.is_empty() {
+        return Some(selection(bank, select_by_stack(bank, 0, k, larger)));
+    }
+    let room = bank.len().saturating_sub(k);
+    let first = (0..=room.min(bank.len().saturating_sub(1)))
+        .filter(|idx| bank.as_bytes()[*idx] != b'0')
+        .min_by_key(|idx| bank.as_bytes()[*idx])?;
+    let mut indices = vec![first];
+    indices.extend(select_by_stack(
+        &bank[first + 1..],
+        first + 1,
+        k - 1,
+        larger,
+    ));
+    Some(selection(bank, indices))
+}
+
+/// The largest `k` neighbouring digits of `bank`, the first of equals, or the
+/// whole bank if it is shorter.
+pub fn best_window(bank: &str, k: usize) -> Selection {
+    let k = k.min(bank.len());
+    let start = (0..=bank.len() - k)
+        .rev()
+        .max_by_key(|start| &bank[*start..start + k])
+        .unwrap();
+    selection(bank, (start..start + k).collect())
+}
+
+/// The indices of the `n` banks whose selections give the most joltage, best
+/// first and equals in bank order.
+pub fn top_banks(selections: &[Selection], n: usize) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..selections.len()).collect();
+    order.sort_by(|a, b| {
+        selections[*b]
+            .joltage_key()
+            .cmp(&selections[*a].joltage_key())
+    });
+    order.truncate(n);
+    order
+}
+
+/// The indices of `k` digits of `bank` in one pass, each digit popping those
+/// before it off a stack while `pops(top, digit)` and enough digits remain to
+/// make up `k`. `offset` is added to every index.
+fn select_by_stack(bank: &str, offset: usize, k: usize, pops: fn(u8, u8) -> bool) -> Vec<usize> {
+    let bytes = bank.as_bytes();
+    let mut drops = bytes.len().saturating_sub(k);
+    let mut stack: Vec<usize> = Vec::with_capacity(bytes.len());
+    for (idx, digit) in bytes.iter().enumerate() {
+        while drops > 0 && stack.last().is_some_and(|top| pops(bytes[*top], *digit)) {
+            stack.pop();
+            drops -= 1;
         }
+        stack.push(idx);
+    }
+    stack.truncate(k);
+    stack.iter().map(|idx| idx + offset).collect()
+}
+
+fn selection(bank: &str, indices: Vec<usize>) -> Selection {
+    Selection {
+        digits: indices
+            .iter()
+            .map(|idx| char::from(bank.as_bytes()[*idx]))
+            .collect(),
+        indices,
+    }
+}
+
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed<'a>(Vec<&'a str>);
+
+    /// `size` banks of a hundred batteries.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(3);
+        (0..size)
+            .map(|_| {
+                (0..100)
+                    .map(|_| char::from(b'1' + rng.range(0, 9) as u8))
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed<'_>, ParseError> {
+        Ok(Parsed(super::parse(input)?))
     }
 
-    d.to_digit(10).unwrap() as u64 * 10u64.pow(num_batteries as u32 - 1)
-        + max_joltage(&bank[idx + 1..], num_batteries - 1)
+    pub fn parse(input: &str) -> Parsed<'_> {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> Result<Count, solution::Error> {
+        total_joltage(&parsed.0, 2, Pick::Max, 0, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, solution::Error> {
+        total_joltage(&parsed.0, 12, Pick::Max, 0, &mut NoTrace)
+    }
+
+    pub fn select_max(bank: &str, k: usize) -> Selection {
+        super::select_max(bank, k)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::solutions::day3::Day3Solver;
 
-    use super::solution::Solver;
+    use super::solution::{Solver, assert_line_ending_variants};
+    use super::*;
+    use proptest::prelude::*;
 
-    #[test]
-    fn test_solve() {
-        let input = r#"987654321111111
+    const INPUT: &str = r#"987654321111111
 811111111111119
 234234234234278
 818181911112111"#;
 
-        let solution = Day3Solver.solve(input);
+    #[test]
+    fn test_solve() {
+        let solution = Day3Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "357");
         assert_eq!(solution.part2, "3121910778619");
     }
+
+    #[test]
+    fn test_many_batteries() {
+        let bank = "98765432109876543210987654321";
+        let joltage = max_joltage(bank, 25);
+        if cfg!(feature = "bigint") {
+            assert_eq!(joltage.unwrap().to_string(), "9876549876543210987654321");
+        } else {
+            assert_eq!(joltage, Err(super::Overflow));
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day3Solver, INPUT);
+    }
+
+    #[test]
+    fn test_select_max() {
+        let selection = select_max("818181911112111", 12);
+        assert_eq!(selection.digits, "888911112111");
+        assert_eq!(
+            selection.indices,
+            vec![0, 2, 4, 6, 7, 8, 9, 10, 11, 12, 13, 14]
+        );
+        assert_eq!(select_max("818181911112111", 2).indices, vec![6, 11]);
+        assert_eq!(select_max("42", 5).digits, "42");
+
+        let bank = "9".repeat(50);
+        let selection = select_max(&bank, 40);
+        assert_eq!(selection.digits, "9".repeat(40));
+        assert_eq!(selection.indices, (0..40).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_select_min() {
+        let selection = select_min("818181911112111", 4, true).unwrap();
+        assert_eq!(selection.digits, "1111");
+        assert_eq!(selection.indices, vec![1, 3, 5, 7]);
+        assert_eq!(select_min("3021", 2, true).unwrap().digits, "01");
+        assert_eq!(select_min("3021", 2, false).unwrap().digits, "21");
+        assert_eq!(select_min("3021", 3, false).unwrap().indices, vec![0, 1, 3]);
+        assert_eq!(select_min("0012", 3, false), None);
+        assert_eq!(select_min("0012", 2, false).unwrap().digits, "12");
+    }
+
+    #[test]
+    fn test_best_window() {
+        let selection = best_window("818181911112111", 3);
+        assert_eq!(selection.digits, "911");
+        assert_eq!(selection.indices, vec![6, 7, 8]);
+        assert_eq!(best_window("1919", 2).indices, vec![1, 2]);
+        assert_eq!(best_window("42", 5).digits, "42");
+    }
+
+    #[test]
+    fn test_top_banks() {
+        let selections: Vec<Selection> = ["19", "0091", "91", "19"]
+            .iter()
+            .map(|digits| selection(digits, (0..digits.len()).collect()))
+            .collect();
+        assert_eq!(top_banks(&selections, 2), vec![1, 2]);
+        assert_eq!(top_banks(&selections, 10), vec![1, 2, 0, 3]);
+    }
+
+    #[test]
+    fn test_params() {
+        let solve = |input: &str, pairs: &[(&str, &str)]| {
+            let mut params = solution::Params::new();
+            for (key, value) in pairs {
+                params.set(*key, *value);
+            }
+            Day3Solver
+                .solve_with(input, &params, &mut crate::trace::NoTrace)
+                .map(|solution| (solution.part1, solution.part2))
+                .map_err(|err| err.to_string())
+        };
+        let answers = |part1: &str, part2: &str| Ok((part1.to_string(), part2.to_string()));
+
+        assert_eq!(
+            solve(INPUT, &[("select", "min")]),
+            answers("55", "1100577568611")
+        );
+        assert_eq!(
+            solve(INPUT, &[("select", "window"), ("top", "1")]),
+            answers("98", "987654321111")
+        );
+        assert_eq!(
+            solve(INPUT, &[("top", "2")]),
+            answers("190", "1876565433222")
+        );
+
+        let zeros = "100000000000000";
+        assert_eq!(solve(zeros, &[("select", "min")]), answers("0", "0"));
+        assert_eq!(
+            solve(zeros, &[("select", "min"), ("leading_zero", "false")]),
+            answers("10", "100000000000")
+        );
+        assert_eq!(
+            solve(
+                "000000000000000",
+                &[("select", "min"), ("leading_zero", "false")]
+            ),
+            Err("no solution: bank 1 has no 2 batteries starting with a nonzero digit".to_string())
+        );
+        assert_eq!(
+            solve(INPUT, &[("select", "middle")]),
+            Err(
+                "invalid parameter: `middle` is not a selection, expected `max`, `min` or `window`"
+                    .to_string()
+            )
+        );
+    }
+
+    fn max_joltage(bank: &str, num_batteries: u8) -> Result<Count, Overflow> {
+        select_max(bank, num_batteries as usize).joltage()
+    }
+
+    /// The recursive search [`select_max`] replaced, rescanning the bank for
+    /// each digit, kept as a reference.
+    fn max_joltage_by_rescanning(bank: &str, num_batteries: u8) -> Result<Count, Overflow> {
+        if num_batteries == 0 {
+            return Ok(Count::zero());
+        }
+        let mut d = '0';
+        let mut idx = 0;
+
+        for (i, c) in bank[0..(bank.len() - (num_batteries as usize - 1))]
+            .chars()
+            .enumerate()
+        {
+            if c == '9' {
+                d = c;
+                idx = i;
+                break;
+            }
+            if c > d {
+                d = c;
+                idx = i;
+            }
+        }
+
+        let place = Count::from(10).checked_pow(num_batteries as u32 - 1)?;
+        Count::from(d.to_digit(10).unwrap() as u64)
+            .checked_mul(&place)?
+            .checked_add(&max_joltage_by_rescanning(
+                &bank[idx + 1..],
+                num_batteries - 1,
+            )?)
+    }
+
+    proptest! {
+        #[test]
+        fn prop_joltage_has_k_digits(bank in "[1-9]{12,40}", k in 1..=12u8) {
+            let joltage = max_joltage(&bank, k).unwrap().to_string();
+            prop_assert_eq!(joltage.len(), k as usize);
+            // No k-digit subsequence beats it, in particular not the first k digits.
+            prop_assert!(joltage.as_str() >= &bank[..k as usize]);
+        }
+
+        #[test]
+        fn prop_stack_matches_rescanning(bank in "[0-9]{19,60}", k in 0..=19usize) {
+            let selection = select_max(&bank, k);
+            prop_assert_eq!(selection.joltage(), max_joltage_by_rescanning(&bank, k as u8));
+            prop_assert_eq!(selection.digits.len(), k);
+            prop_assert!(selection.indices.windows(2).all(|pair| pair[0] < pair[1]));
+            let picked: String = selection.indices.iter().map(|idx| &bank[*idx..=*idx]).collect();
+            prop_assert_eq!(picked, selection.digits);
+        }
+
+        #[test]
+        fn prop_min_matches_brute_force(
+            bank in "[0-3]{1,10}",
+            k in 0..=10usize,
+            leading_zero in any::<bool>(),
+        ) {
+            let k = k.min(bank.len());
+            let smallest = (0u32..1 << bank.len())
+                .filter(|mask| mask.count_ones() as usize == k)
+                .map(|mask| {
+                    (0..bank.len())
+                        .filter(|idx| mask & 1 << idx != 0)
+                        .map(|idx| &bank[idx..=idx])
+                        .collect::<String>()
+                })
+                .filter(|digits| leading_zero || !digits.starts_with('0'))
+                .min();
+            prop_assert_eq!(select_min(&bank, k, leading_zero).map(|selection| selection.digits), smallest);
+        }
+
+        #[test]
+        fn prop_window_matches_brute_force(bank in "[0-9]{1,30}", k in 1..=30usize) {
+            let k = k.min(bank.len());
+            let selection = best_window(&bank, k);
+            let best = (0..=bank.len() - k).map(|start| &bank[start..start + k]).max().unwrap();
+            prop_assert_eq!(selection.digits.as_str(), best);
+            prop_assert_eq!(&bank[selection.indices[0]..selection.indices[0] + k], best);
+            prop_assert_eq!(selection.indices.len(), k);
+        }
+
+        #[test]
+        fn prop_parse_accepts_digit_banks(banks in prop::collection::vec("[0-9]{12,30}", 1..10)) {
+            let input = banks.join("\n");
+            prop_assert_eq!(super::parse(&input).unwrap(), banks);
+        }
+    }
 }
@@ -1,105 +1,472 @@
-use std::{collections::HashSet, hash::Hash};
+use std::str::FromStr;
 
+use crate::parsing::{self, ParseError};
 use crate::solutions::solution;
+use crate::trace::{NoTrace, Trace};
+use crate::viz::Frame;
 
-#[derive(PartialEq, Eq, Hash, Clone)]
-struct Point(i64, i64);
+/// The rolls, one bit per cell of the grid they sit in, row after row.
+#[derive(Clone)]
+struct Rolls {
+    bits: Vec<u64>,
+    rows: usize,
+    cols: usize,
+}
+
+impl Rolls {
+    fn new(rows: usize, cols: usize) -> Self {
+        Rolls {
+            bits: vec![0; (rows * cols).div_ceil(64)],
+            rows,
+            cols,
+        }
+    }
+
+    fn contains(&self, cell: usize) -> bool {
+        self.bits[cell / 64] >> (cell % 64) & 1 == 1
+    }
+
+    fn insert(&mut self, cell: usize) {
+        self.bits[cell / 64] |= 1 << (cell % 64);
+    }
+
+    fn remove(&mut self, cell: usize) {
+        self.bits[cell / 64] &= !(1 << (cell % 64));
+    }
+
+    fn len(&self) -> usize {
+        self.bits
+            .iter()
+            .map(|word| word.count_ones() as usize)
+            .sum()
+    }
+
+    /// The cells holding rolls, in order.
+    fn cells(&self) -> impl Iterator<Item = usize> + '_ {
+        self.bits.iter().enumerate().flat_map(|(idx, word)| {
+            let mut word = *word;
+            std::iter::from_fn(move || {
+                (word != 0).then(|| {
+                    let bit = word.trailing_zeros() as usize;
+                    word &= word - 1;
+                    idx * 64 + bit
+                })
+            })
+        })
+    }
+}
+
+/// When a roll can be removed: if fewer than `threshold` of the cells in its
+/// neighbourhood hold rolls.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rule {
+    pub threshold: usize,
+    pub neighbourhood: Neighbourhood,
+    /// How many steps away a cell can be and still count as a neighbour, up
+    /// to [`MAX_RADIUS`].
+    pub radius: u32,
+    /// Whether cells past an edge of the grid come back in at the other.
+    pub wrap: bool,
+}
+
+/// The puzzle's rule: fewer than 4 of the 8 rolls around.
+impl Default for Rule {
+    fn default() -> Self {
+        Rule {
+            threshold: 4,
+            neighbourhood: Neighbourhood::Moore,
+            radius: 1,
+            wrap: false,
+        }
+    }
+}
+
+impl Rule {
+    /// The steps from a cell to its neighbours.
+    fn offsets(&self) -> Vec<(i64, i64)> {
+        let radius = i64::from(self.radius);
+        let mut offsets = vec![];
+        for x in -radius..=radius {
+            for y in -radius..=radius {
+                let within = match self.neighbourhood {
+                    Neighbourhood::VonNeumann => x.abs() + y.abs() <= radius,
+                    Neighbourhood::Moore => true,
+                };
+                if within && (x, y) != (0, 0) {
+                    offsets.push((x, y));
+                }
+            }
+        }
+        offsets
+    }
+}
+
+/// The largest radius a rule can have. Every cell checks each of the
+/// (2r+1)² steps around it, so a few hundred is already plenty of work.
+pub const MAX_RADIUS: u32 = 10;
+
+/// Which cells within the radius count as neighbours.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Neighbourhood {
+    /// Those a radius of steps away along rows and columns, a diamond.
+    VonNeumann,
+    /// Those a radius of steps away in any direction, a square.
+    Moore,
+}
+
+impl FromStr for Neighbourhood {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "von-neumann" => Ok(Neighbourhood::VonNeumann),
+            "moore" => Ok(Neighbourhood::Moore),
+            _ => Err(format!("expected `von-neumann` or `moore`, found `{s}`")),
+        }
+    }
+}
+
+/// The rule from the `threshold`, `neighbourhood`, `radius` and `wrap` params.
+pub fn rule(params: &solution::Params) -> Result<Rule, solution::Error> {
+    let default = Rule::default();
+    Ok(Rule {
+        threshold: params.get("threshold", default.threshold)?,
+        neighbourhood: params.get("neighbourhood", default.neighbourhood)?,
+        radius: params.get("radius", default.radius)?,
+        wrap: params.get("wrap", default.wrap)?,
+    })
+}
 
 pub struct Day4Solver;
 
 impl solution::Solver for Day4Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let rule = rule(params)?;
+        let rolls = rule_rolls(input, &rule)?;
+        Ok(solution::Solution {
+            part1: part1(&rolls, &rule).to_string(),
+            part2: part2(rolls, &rule, trace).to_string(),
+        })
+    }
+
+    fn visualize(
+        &self,
+        input: &str,
+        params: &solution::Params,
+    ) -> Result<Vec<Frame>, solution::Error> {
+        let rule = rule(params)?;
+        let mut rolls = rule_rolls(input, &rule)?;
+
+        let mut frames = vec![Frame::text("wave-0", render(&rolls, &[]))];
+        for (idx, wave) in removal_waves(rolls.clone(), &rule, &mut NoTrace)
+            .iter()
+            .enumerate()
+        {
+            frames.push(Frame::text(
+                format!("wave-{}", idx + 1),
+                render(&rolls, wave),
+            ));
+            wave.iter().for_each(|cell| rolls.remove(*cell));
         }
+        Ok(frames)
+    }
+}
+
+/// Draws the grid with the rolls about to be removed marked as `x`.
+fn render(rolls: &Rolls, removed: &[usize]) -> String {
+    let mut cells: Vec<char> = (0..rolls.rows * rolls.cols)
+        .map(|cell| if rolls.contains(cell) { '@' } else { '.' })
+        .collect();
+    for cell in removed {
+        cells[*cell] = 'x';
+    }
+    let mut text = String::with_capacity(rolls.rows * (rolls.cols + 1));
+    for row in cells.chunks(rolls.cols.max(1)) {
+        text.extend(row);
+        text.push('\n');
     }
+    text
 }
 
-fn part2(input: &str) -> usize {
-    let mut points = get_rolls_points(input);
-    let initial_size = points.len();
-    let mut to_check = points.iter().cloned().collect::<HashSet<Point>>();
+fn part2(rolls: Rolls, rule: &Rule, trace: &mut dyn Trace) -> usize {
+    removal_waves(rolls, rule, trace).iter().map(Vec::len).sum()
+}
 
-    loop {
-        let to_remove = to_check
-            .iter()
-            .filter(|p| can_remove(p, &points))
-            .cloned()
-            .collect::<HashSet<Point>>();
+/// What removing rolls until no more can be leaves behind.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Removal {
+    /// The rolls left, drawn like the input.
+    pub grid: String,
+    /// The wave that removed each cell's roll, counting from 1, row by row,
+    /// or `None` for cells without a roll or whose roll stayed.
+    pub waves: Vec<Vec<Option<usize>>>,
+    /// The rolls left after each wave, drawn like the input, if asked for.
+    pub steps: Vec<String>,
+}
+
+impl Removal {
+    /// The waves as text, a line per row of space-separated cells: the wave
+    /// that removed the cell's roll, `@` if its roll stayed or `.` if it had
+    /// none.
+    pub fn wave_map(&self) -> String {
+        let mut text = String::new();
+        for (row, line) in self.waves.iter().zip(self.grid.lines()) {
+            let cells: Vec<String> = row
+                .iter()
+                .zip(line.chars())
+                .map(|(wave, c)| match wave {
+                    Some(wave) => wave.to_string(),
+                    None => c.to_string(),
+                })
+                .collect();
+            text.push_str(&cells.join(" "));
+            text.push('\n');
+        }
+        text
+    }
+}
 
+/// Removes rolls under `rule` until no more can be, keeping the grid after
+/// every wave too if `steps`.
+pub fn removal(input: &str, rule: &Rule, steps: bool) -> Result<Removal, solution::Error> {
+    let mut rolls = rule_rolls(input, rule)?;
+    let mut removed_by = vec![None; rolls.rows * rolls.cols];
+    let mut removal = Removal {
+        grid: String::new(),
+        waves: vec![],
+        steps: vec![],
+    };
+    for (idx, wave) in removal_waves(rolls.clone(), rule, &mut NoTrace)
+        .iter()
+        .enumerate()
+    {
+        for cell in wave {
+            rolls.remove(*cell);
+            removed_by[*cell] = Some(idx + 1);
+        }
+        if steps {
+            removal.steps.push(render(&rolls, &[]));
+        }
+    }
+    removal.grid = render(&rolls, &[]);
+    removal.waves = removed_by
+        .chunks(rolls.cols.max(1))
+        .map(<[Option<usize>]>::to_vec)
+        .collect();
+    Ok(removal)
+}
+
+/// How many rolls each wave removes under `rule` until no more can be, so
+/// as many entries as it takes waves to stabilise.
+pub fn removed_per_wave(input: &str, rule: &Rule) -> Result<Vec<usize>, solution::Error> {
+    let rolls = rule_rolls(input, rule)?;
+    Ok(removal_waves(rolls, rule, &mut NoTrace)
+        .iter()
+        .map(Vec::len)
+        .collect())
+}
+
+/// The cells of the rolls removed by each wave, in order, until no more can
+/// be removed. Every roll keeps a count of the rolls around it, and removing
+/// a wave lowers its neighbours' counts: those that drop below the threshold
+/// make up the next wave, so each roll is only looked at when a neighbour
+/// goes.
+fn removal_waves(mut rolls: Rolls, rule: &Rule, trace: &mut dyn Trace) -> Vec<Vec<usize>> {
+    let neighbours = Neighbours::new(&rolls, rule);
+    let mut counts = neighbour_counts(&rolls, &neighbours);
+    let mut left = rolls.len();
+    let mut waves = vec![];
+    let mut to_remove: Vec<usize> = rolls
+        .cells()
+        .filter(|cell| (counts[*cell] as usize) < rule.threshold)
+        .collect();
+
+    for wave in 1.. {
         if to_remove.is_empty() {
+            trace.step(
+                "stable",
+                format_args!("after {} waves, {left} left", wave - 1),
+            );
             break;
         }
 
-        to_remove.iter().for_each(|p| {
-            points.remove(p);
-        });
+        to_remove.iter().for_each(|cell| rolls.remove(*cell));
+        left -= to_remove.len();
+        trace.step(
+            "wave",
+            format_args!("{wave}: removed {}, {left} left", to_remove.len()),
+        );
 
-        to_check.clear();
-        for p in to_remove {
-            for a in adjacent_points(&p) {
-                if points.contains(&a) {
-                    to_check.insert(a);
+        let mut next = vec![];
+        for cell in &to_remove {
+            for neighbour in neighbours.of(*cell) {
+                if rolls.contains(neighbour) {
+                    counts[neighbour] -= 1;
+                    // Counts only go down, so each roll drops below the
+                    // threshold once.
+                    if counts[neighbour] as usize + 1 == rule.threshold {
+                        next.push(neighbour);
+                    }
                 }
             }
         }
+        next.sort_unstable();
+        waves.push(std::mem::replace(&mut to_remove, next));
     }
 
-    initial_size - points.len()
+    waves
 }
 
-fn part1(input: &str) -> usize {
-    let points = get_rolls_points(input);
-    points.iter().filter(|p| can_remove(p, &points)).count()
+fn part1(rolls: &Rolls, rule: &Rule) -> usize {
+    let counts = neighbour_counts(rolls, &Neighbours::new(rolls, rule));
+    rolls
+        .cells()
+        .filter(|cell| (counts[*cell] as usize) < rule.threshold)
+        .count()
 }
 
-fn can_remove(point: &Point, points: &HashSet<Point>) -> bool {
-    adjacent_rolls(point, points) < 4
+/// How many rolls are around each roll, by cell.
+fn neighbour_counts(rolls: &Rolls, neighbours: &Neighbours) -> Vec<u32> {
+    let mut counts = vec![0; rolls.rows * rolls.cols];
+    for cell in rolls.cells() {
+        counts[cell] = neighbours
+            .of(cell)
+            .filter(|neighbour| rolls.contains(*neighbour))
+            .count() as u32;
+    }
+    counts
 }
 
-fn adjacent_rolls(point: &Point, rolls_points: &HashSet<Point>) -> usize {
-    adjacent_points(point)
-        .iter()
-        .filter(|p| rolls_points.contains(p))
-        .count()
+/// The neighbours of each cell of a grid under a rule.
+struct Neighbours {
+    offsets: Vec<(i64, i64)>,
+    rows: i64,
+    cols: i64,
+    wrap: bool,
+}
+
+impl Neighbours {
+    fn new(rolls: &Rolls, rule: &Rule) -> Self {
+        let (rows, cols) = (rolls.rows as i64, rolls.cols as i64);
+        let mut offsets = rule.offsets();
+        if rule.wrap && rows > 0 && cols > 0 {
+            // A wrapped offset lands where its remainders do, so on a small
+            // grid several offsets reach the same cell, which counts once, or
+            // the cell itself, which doesn't count.
+            offsets = offsets
+                .iter()
+                .map(|(x, y)| (x.rem_euclid(rows), y.rem_euclid(cols)))
+                .filter(|offset| *offset != (0, 0))
+                .collect();
+            offsets.sort_unstable();
+            offsets.dedup();
+        }
+        Neighbours {
+            offsets,
+            rows,
+            cols,
+            wrap: rule.wrap,
+        }
+    }
+
+    fn of(&self, cell: usize) -> impl Iterator<Item = usize> + '_ {
+        let (row, col) = (cell as i64 / self.cols, cell as i64 % self.cols);
+        self.offsets.iter().filter_map(move |(x, y)| {
+            let (mut row, mut col) = (row + x, col + y);
+            if self.wrap {
+                (row, col) = (row % self.rows, col % self.cols);
+            }
+            ((0..self.rows).contains(&row) && (0..self.cols).contains(&col))
+                .then_some((row * self.cols + col) as usize)
+        })
+    }
 }
 
-fn adjacent_points(point: &Point) -> Vec<Point> {
-    let mut result = Vec::new();
+/// The rolls in `input`, if `rule`'s radius is at most [`MAX_RADIUS`].
+fn rule_rolls(input: &str, rule: &Rule) -> Result<Rolls, solution::Error> {
+    if rule.radius > MAX_RADIUS {
+        return Err(solution::Error::Param(format!(
+            "a radius of {} is more than the largest, {MAX_RADIUS}",
+            rule.radius
+        )));
+    }
+    Ok(get_rolls_points(input)?)
+}
 
-    for x in point.0 - 1..=point.0 + 1 {
-        for y in point.1 - 1..=point.1 + 1 {
-            if x == point.0 && y == point.1 {
-                continue;
+fn get_rolls_points(input: &str) -> Result<Rolls, ParseError> {
+    let grid = parsing::grid(parsing::lines(input), |c| match c {
+        '@' => Some(true),
+        '.' => Some(false),
+        _ => None,
+    })?;
+
+    let cols = grid.iter().map(Vec::len).max().unwrap_or(0);
+    let mut rolls = Rolls::new(grid.len(), cols);
+    for (row, line) in grid.iter().enumerate() {
+        for (col, roll) in line.iter().enumerate() {
+            if *roll {
+                rolls.insert(row * cols + col);
             }
-            result.push(Point(x, y));
         }
     }
-    result
+    Ok(rolls)
 }
 
-fn get_rolls_points(input: &str) -> HashSet<Point> {
-    input
-        .lines()
-        .enumerate()
-        .flat_map(|(row, l)| {
-            l.char_indices()
-                .map(move |(col, c)| (Point(row as i64, col as i64), c))
-        })
-        .filter_map(|(p, c)| if c == '@' { Some(p) } else { None })
-        .collect::<HashSet<Point>>()
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+
+    pub struct Parsed(Rolls);
+
+    /// A `size` by `size` grid, about two thirds of it rolls.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(4);
+        (0..size)
+            .map(|_| {
+                (0..size)
+                    .map(|_| if rng.chance(65) { '@' } else { '.' })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(get_rolls_points(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> usize {
+        super::part1(&parsed.0, &Rule::default())
+    }
+
+    /// Includes cloning the rolls, which part two removes as it goes.
+    pub fn part2(parsed: &Parsed) -> usize {
+        super::part2(parsed.0.clone(), &Rule::default(), &mut NoTrace)
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use crate::solutions::day4::Day4Solver;
+    use crate::trace::{NoTrace, RecordTrace};
 
-    use super::solution::Solver;
+    use super::solution::{Params, Solver, assert_line_ending_variants};
+    use super::*;
+    use proptest::prelude::*;
+    use std::collections::HashSet;
 
-    #[test]
-    fn test_solve() {
-        let input = r#"..@@.@@@@.
+    const INPUT: &str = r#"..@@.@@@@.
 @@@.@.@.@@
 @@@@@.@.@@
 @.@@@@..@.
@@ -110,8 +477,281 @@ mod tests {
 .@@@@@@@@.
 @.@.@@@.@."#;
 
-        let solution = Day4Solver.solve(input);
+    #[test]
+    fn test_solve() {
+        let solution = Day4Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "13");
         assert_eq!(solution.part2, "43");
     }
+
+    #[test]
+    fn test_visualize() {
+        let frames = Day4Solver.visualize(INPUT, &Params::new()).unwrap();
+        assert_eq!(frames.len(), 10);
+        assert_eq!(frames[0].bytes(), format!("{INPUT}\n").into_bytes());
+        assert_eq!(
+            String::from_utf8(frames[1].bytes()).unwrap().lines().next(),
+            Some("..xx.xx@x.")
+        );
+    }
+
+    #[test]
+    fn test_rules() {
+        let waves = |input: &str, rule: Rule| removed_per_wave(input, &rule).unwrap();
+        let rule = |threshold, neighbourhood, radius, wrap| Rule {
+            threshold,
+            neighbourhood,
+            radius,
+            wrap,
+        };
+        use Neighbourhood::{Moore, VonNeumann};
+
+        let puzzle = waves(INPUT, Rule::default());
+        assert_eq!(puzzle.len(), 9);
+        assert_eq!((puzzle[0], puzzle.iter().sum()), (13, 43));
+
+        let square = "@@@\n@@@\n@@@";
+        assert_eq!(
+            waves(square, rule(2, VonNeumann, 1, false)),
+            Vec::<usize>::new()
+        );
+        assert_eq!(waves(square, rule(3, VonNeumann, 1, false)), vec![4, 4, 1]);
+        assert_eq!(waves(square, rule(4, Moore, 1, false)), vec![4, 4, 1]);
+        assert_eq!(waves(square, rule(8, Moore, 1, true)), Vec::<usize>::new());
+        assert_eq!(waves(square, rule(9, Moore, 1, true)), vec![9]);
+        assert_eq!(waves(square, rule(1, Moore, 0, false)), vec![9]);
+
+        assert_eq!(waves("@@@@@", rule(3, VonNeumann, 2, false)), vec![2, 3]);
+        assert_eq!(waves("@@@@@", rule(2, Moore, 1, true)), Vec::<usize>::new());
+        // The other roll is left and right of each, but counts once.
+        assert_eq!(waves("@@", rule(1, Moore, 1, true)), Vec::<usize>::new());
+        assert_eq!(waves("@@", rule(2, Moore, 1, true)), vec![2]);
+    }
+
+    #[test]
+    fn test_rule_params() {
+        let params = Params::from([("threshold", "3"), ("neighbourhood", "von-neumann")]);
+        let mut trace = RecordTrace::default();
+        let solution = Day4Solver
+            .solve_with("@@@\n@@@\n@@@", &params, &mut trace)
+            .unwrap();
+        assert_eq!(
+            (solution.part1.as_str(), solution.part2.as_str()),
+            ("4", "9")
+        );
+        assert_eq!(
+            trace.details("wave"),
+            vec![
+                "1: removed 4, 5 left",
+                "2: removed 4, 1 left",
+                "3: removed 1, 0 left"
+            ]
+        );
+        assert_eq!(trace.details("stable"), vec!["after 3 waves, 0 left"]);
+
+        let params = Params::from([("radius", "100000")]);
+        assert_eq!(
+            Day4Solver
+                .solve_with(INPUT, &params, &mut NoTrace)
+                .unwrap_err()
+                .to_string(),
+            "invalid parameter: a radius of 100000 is more than the largest, 10"
+        );
+        let params = Params::from([("radius", "11")]);
+        assert!(Day4Solver.solve_with(INPUT, &params, &mut NoTrace).is_err());
+        let params = Params::from([("radius", "10"), ("wrap", "true")]);
+        assert!(Day4Solver.solve_with(INPUT, &params, &mut NoTrace).is_ok());
+
+        let params = Params::from([("neighbourhood", "hex")]);
+        assert_eq!(
+            Day4Solver
+                .solve_with(INPUT, &params, &mut NoTrace)
+                .unwrap_err()
+                .to_string(),
+            "invalid parameter: `hex` is not a valid `neighbourhood`"
+        );
+    }
+
+    #[test]
+    fn test_removal() {
+        let removal = removal(INPUT, &Rule::default(), true).unwrap();
+        assert_eq!(
+            removal.grid,
+            "..........
+..........
+..........
+....@@....
+...@@@@...
+...@@@@@..
+...@.@.@@.
+...@@.@@@.
+...@@@@@..
+....@@@...
+"
+        );
+        assert_eq!(removal.steps.len(), 9);
+        assert_eq!(removal.steps.last(), Some(&removal.grid));
+        // Each step leaves the last one's rolls but those its wave removed.
+        let visualized = Day4Solver.visualize(INPUT, &Params::new()).unwrap();
+        for (step, frame) in removal.steps.iter().zip(&visualized[1..]) {
+            let frame = String::from_utf8(frame.bytes()).unwrap();
+            assert_eq!(*step, frame.replace('x', "."));
+        }
+
+        assert_eq!(removal.waves.len(), 10);
+        assert_eq!(removal.waves[0][2], Some(1));
+        assert_eq!(removal.waves[0][0], None);
+        let removed = removal.waves.iter().flatten().flatten().count();
+        assert_eq!(removed, 43);
+        assert_eq!(
+            removal.wave_map().lines().next(),
+            Some(". . 1 1 . 1 1 2 1 .")
+        );
+        assert_eq!(
+            removal.wave_map().lines().nth(3),
+            Some("2 . 6 9 @ @ . . 2 .")
+        );
+
+        assert!(
+            super::removal(INPUT, &Rule::default(), false)
+                .unwrap()
+                .steps
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_large_grids() {
+        let full = vec!["@".repeat(2000); 2000].join("\n");
+        let solution = Day4Solver.solve(&full).unwrap();
+        // Only the corners have fewer than 4 neighbours, and taking them
+        // leaves the rolls next to them with 4.
+        assert_eq!(
+            (solution.part1.as_str(), solution.part2.as_str()),
+            ("4", "4")
+        );
+
+        let striped = format!("{}\n{}\n", "@".repeat(2000), ".".repeat(2000)).repeat(1000);
+        let solution = Day4Solver.solve(&striped).unwrap();
+        assert_eq!(solution.part1, "2000000");
+        assert_eq!(solution.part2, "2000000");
+    }
+
+    /// The `HashSet` simulation the bit grid replaced, checking the rolls
+    /// next to each removed one for the next wave, kept as a reference.
+    fn removal_waves_by_hash_set(input: &str, rule: &Rule) -> Vec<Vec<usize>> {
+        let lines: Vec<&str> = input.lines().collect();
+        let rows = lines.len() as i64;
+        let cols = lines.iter().map(|line| line.len()).max().unwrap_or(0) as i64;
+        let mut points: HashSet<(i64, i64)> = HashSet::new();
+        for (row, line) in lines.iter().enumerate() {
+            for (col, c) in line.chars().enumerate() {
+                if c == '@' {
+                    points.insert((row as i64, col as i64));
+                }
+            }
+        }
+        let adjacent_points = |point: &(i64, i64)| {
+            let mut result = Vec::new();
+            for (x, y) in rule.offsets() {
+                let mut adjacent = (point.0 + x, point.1 + y);
+                if rule.wrap {
+                    adjacent = (adjacent.0.rem_euclid(rows), adjacent.1.rem_euclid(cols));
+                    if adjacent == *point || result.contains(&adjacent) {
+                        continue;
+                    }
+                }
+                result.push(adjacent);
+            }
+            result
+        };
+
+        let mut waves = vec![];
+        let mut to_check = points.clone();
+        loop {
+            let to_remove: HashSet<(i64, i64)> = to_check
+                .iter()
+                .filter(|p| {
+                    let rolls = adjacent_points(p)
+                        .iter()
+                        .filter(|a| points.contains(a))
+                        .count();
+                    rolls < rule.threshold
+                })
+                .cloned()
+                .collect();
+            if to_remove.is_empty() {
+                return waves;
+            }
+            to_remove.iter().for_each(|p| {
+                points.remove(p);
+            });
+            to_check = to_remove
+                .iter()
+                .flat_map(adjacent_points)
+                .filter(|a| points.contains(a))
+                .collect();
+            let mut cells: Vec<usize> = to_remove
+                .iter()
+                .map(|(row, col)| (row * cols + col) as usize)
+                .collect();
+            cells.sort_unstable();
+            waves.push(cells);
+        }
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day4Solver, INPUT);
+    }
+
+    fn grids() -> impl Strategy<Value = String> {
+        (1..20usize, 1..20usize).prop_flat_map(|(rows, cols)| {
+            prop::collection::vec(prop::collection::vec(any::<bool>(), cols), rows).prop_map(
+                |rows| {
+                    rows.iter()
+                        .map(|row| {
+                            row.iter()
+                                .map(|roll| if *roll { '@' } else { '.' })
+                                .collect()
+                        })
+                        .collect::<Vec<String>>()
+                        .join("\n")
+                },
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_render_round_trip(grid in grids()) {
+            let rolls = get_rolls_points(&grid).unwrap();
+            let rendered = render(&rolls, &[]);
+            prop_assert_eq!(rendered.trim_end(), grid);
+        }
+
+        #[test]
+        fn prop_matches_hash_set(
+            grid in grids(),
+            threshold in 0..10usize,
+            moore in any::<bool>(),
+            radius in 0..3u32,
+            wrap in any::<bool>(),
+        ) {
+            let neighbourhood = if moore { Neighbourhood::Moore } else { Neighbourhood::VonNeumann };
+            let rule = Rule { threshold, neighbourhood, radius, wrap };
+            let rolls = get_rolls_points(&grid).unwrap();
+            prop_assert_eq!(
+                removal_waves(rolls, &rule, &mut NoTrace),
+                removal_waves_by_hash_set(&grid, &rule)
+            );
+        }
+
+        #[test]
+        fn prop_part2_at_least_part1(grid in grids()) {
+            let rolls = get_rolls_points(&grid).unwrap();
+            let rule = Rule::default();
+            prop_assert!(part2(rolls.clone(), &rule, &mut NoTrace) >= part1(&rolls, &rule));
+        }
+    }
 }
@@ -1,4 +1,7 @@
+use crate::answer::{Count, Overflow};
+use crate::parsing::{self, ParseError};
 use crate::solutions::solution;
+use crate::trace::Trace;
 use std::cmp;
 
 pub struct Day5Solver;
@@ -6,45 +9,46 @@ pub struct Day5Solver;
 struct Range(u64, u64);
 
 impl solution::Solver for Day5Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
-        }
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let (mut ranges, numbers) = parse(input)?;
+        ranges.sort_by_key(|r| r.0);
+        Ok(solution::Solution {
+            part1: part1(&ranges, &numbers).to_string(),
+            part2: part2(&ranges, trace)?.to_string(),
+        })
     }
 }
 
-fn part2(input: &str) -> usize {
-    let (mut ranges, _) = parse(input);
-    ranges.sort_by(|r1, r2| r1.0.cmp(&r2.0));
-
-    merge_ranges(&ranges)
+fn part2(ranges: &[Range], trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    merge_ranges(ranges)
         .iter()
-        .fold(0, |acc, Range(start, end)| acc + (end - start + 1) as usize)
+        .inspect(|Range(start, end)| trace.step("merged range", format_args!("{start}-{end}")))
+        .try_fold(Count::zero(), |acc, Range(start, end)| {
+            acc.checked_add(&Count::from(end - start))?
+                .checked_add(&Count::one())
+        })
 }
 
-fn part1(input: &str) -> usize {
-    let (ranges, numbers) = parse(input);
-    numbers.iter().filter(|n| in_any_range(n, &ranges)).count()
+fn part1(ranges: &[Range], numbers: &[u64]) -> usize {
+    numbers.iter().filter(|n| in_any_range(n, ranges)).count()
 }
 
+/// Merges overlapping ranges, which must be sorted by start.
 fn merge_ranges(ranges: &[Range]) -> Vec<Range> {
-    let (mut start, mut end) = (u64::MAX, u64::MIN);
-    let mut merged = Vec::new();
+    let mut merged: Vec<Range> = Vec::new();
 
     for r in ranges {
-        if r.0 <= end {
-            end = cmp::max(r.1, end);
-        } else {
-            if end != 0 {
-                merged.push(Range(start, end));
-            }
-            start = r.0;
-            end = r.1;
+        match merged.last_mut() {
+            Some(last) if r.0 <= last.1 => last.1 = cmp::max(r.1, last.1),
+            _ => merged.push(Range(r.0, r.1)),
         }
     }
 
-    merged.push(Range(start, end));
     merged
 }
 
@@ -52,27 +56,84 @@ fn in_any_range(num: &u64, ranges: &[Range]) -> bool {
     ranges.iter().any(|r| *num >= r.0 && *num <= r.1)
 }
 
-fn parse(input: &str) -> (Vec<Range>, Vec<u64>) {
-    let ranges_nums: Vec<&str> = input.split("\n\n").take(2).collect();
-    let ranges = ranges_nums[0].lines().map(to_range).collect();
-    let nums = ranges_nums[1].lines().map(|n| n.parse().unwrap()).collect();
-
-    (ranges, nums)
+fn parse(input: &str) -> Result<(Vec<Range>, Vec<u64>), ParseError> {
+    match &parsing::sections(input)[..] {
+        [ranges, nums] => {
+            let ranges = ranges
+                .iter()
+                .map(|line| match line.range()? {
+                    (start, end) if start > end => Err(line.error("range ends before it starts")),
+                    (start, end) => Ok(Range(start, end)),
+                })
+                .collect::<Result<_, _>>()?;
+            let nums = nums.iter().map(|n| n.parse()).collect::<Result<_, _>>()?;
+            Ok((ranges, nums))
+        }
+        _ => Err(ParseError::end_of_input(
+            input,
+            "expected ranges and ingredient IDs separated by a blank line",
+        )),
+    }
 }
 
-fn to_range(line: &str) -> Range {
-    let start_end = line.split("-").take(2).collect::<Vec<&str>>();
-    Range(start_end[0].parse().unwrap(), start_end[1].parse().unwrap())
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed(Vec<Range>, Vec<u64>);
+
+    /// `size` ranges of fresh IDs and `size` IDs to check.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(5);
+        let ranges: Vec<String> = (0..size)
+            .map(|_| {
+                let start = rng.range(0, 1 << 40);
+                format!("{start}-{}", start + rng.range(0, 1 << 30))
+            })
+            .collect();
+        let ids: Vec<String> = (0..size)
+            .map(|_| rng.range(0, 1 << 40).to_string())
+            .collect();
+        format!("{}\n\n{}", ranges.join("\n"), ids.join("\n"))
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        let (mut ranges, numbers) = super::parse(input)?;
+        ranges.sort_by_key(|r| r.0);
+        Ok(Parsed(ranges, numbers))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> usize {
+        super::part1(&parsed.0, &parsed.1)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part2(&parsed.0, &mut NoTrace)
+    }
+
+    /// The number of merged ranges.
+    pub fn merge_ranges(parsed: &Parsed) -> usize {
+        super::merge_ranges(&parsed.0).len()
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Params, Solver, assert_line_ending_variants};
     use crate::solutions::day5::Day5Solver;
+    use crate::trace::RecordTrace;
 
-    #[test]
-    fn test_input() {
-        let input = r#"3-5
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r#"3-5
 10-14
 16-20
 12-18
@@ -84,8 +145,71 @@ mod tests {
 17
 32"#;
 
-        let solution = Day5Solver.solve(input);
+    #[test]
+    fn test_input() {
+        let solution = Day5Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "3");
         assert_eq!(solution.part2, "14")
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day5Solver, INPUT);
+    }
+
+    #[test]
+    fn test_trace() {
+        let mut trace = RecordTrace::default();
+        Day5Solver
+            .solve_with(INPUT, &Params::new(), &mut trace)
+            .unwrap();
+        assert_eq!(trace.details("merged range"), vec!["3-5", "10-20"]);
+    }
+
+    #[test]
+    fn test_extreme_ranges() {
+        let solution = Day5Solver.solve("0-0\n0-18446744073709551615\n\n0");
+        if cfg!(feature = "bigint") {
+            assert_eq!(solution.unwrap().part2, "18446744073709551616");
+        } else {
+            assert!(solution.is_err());
+        }
+        assert!(Day5Solver.solve("10-1\n\n5").is_err());
+    }
+
+    proptest! {
+        #[test]
+        fn prop_merge_ranges(bounds in prop::collection::vec((0..200u64, 0..20u64), 0..20)) {
+            let mut ranges: Vec<Range> =
+                bounds.iter().map(|(start, len)| Range(*start, start + len)).collect();
+            ranges.sort_by_key(|r| r.0);
+            let merged = merge_ranges(&ranges);
+
+            for pair in merged.windows(2) {
+                prop_assert!(pair[0].1 < pair[1].0, "not sorted and disjoint");
+            }
+            for id in 0..=220 {
+                prop_assert_eq!(in_any_range(&id, &merged), in_any_range(&id, &ranges), "{}", id);
+            }
+        }
+
+        #[test]
+        fn prop_parse_print_round_trip(
+            bounds in prop::collection::vec((any::<u64>(), any::<u64>()), 1..10),
+            numbers in prop::collection::vec(any::<u64>(), 1..10),
+        ) {
+            let ranges: Vec<String> = bounds
+                .iter()
+                .map(|(a, b)| format!("{}-{}", a.min(b), a.max(b)))
+                .collect();
+            let numbers: Vec<String> = numbers.iter().map(u64::to_string).collect();
+            let input = format!("{}\n\n{}", ranges.join("\n"), numbers.join("\n"));
+
+            let (parsed_ranges, parsed_numbers) = parse(&input).unwrap();
+            let printed: Vec<String> =
+                parsed_ranges.iter().map(|Range(a, b)| format!("{a}-{b}")).collect();
+            prop_assert_eq!(printed, ranges);
+            prop_assert_eq!(parsed_numbers.iter().map(u64::to_string).collect::<Vec<_>>(), numbers);
+        }
+    }
 }
@@ -1,13 +1,23 @@
+use crate::answer::{Count, Overflow};
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::Trace;
+use std::fmt;
 
 pub struct Day6Solver;
 
 impl solution::Solver for Day6Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
-        }
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let worksheet = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&worksheet, trace)?.to_string(),
+            part2: part2(&worksheet, trace)?.to_string(),
+        })
     }
 }
 
@@ -17,101 +27,260 @@ enum Op {
     Mul,
 }
 
-fn part1(input: &str) -> u64 {
-    let (numbers, operators) = parse(input);
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Add => write!(f, "+"),
+            Op::Mul => write!(f, "*"),
+        }
+    }
+}
+
+struct Worksheet {
+    numbers: Vec<Vec<u64>>,
+    /// Each problem's numbers read top to bottom, one per character column.
+    columns: Vec<Vec<u64>>,
+    operators: Vec<Op>,
+}
 
-    (0..numbers[0].len())
+fn part1(worksheet: &Worksheet, trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    let Worksheet {
+        numbers, operators, ..
+    } = worksheet;
+
+    let results = (0..numbers[0].len())
         .map(|col| {
-            let operation = get_func(&operators[col]);
-            numbers.iter().map(|n| n[col]).reduce(operation).unwrap()
+            let column: Vec<Count> = numbers.iter().map(|n| Count::from(n[col])).collect();
+            apply(&operators[col], &column, "part1 column", trace)
         })
-        .sum()
+        .collect::<Result<Vec<Count>, Overflow>>()?;
+    Count::sum(&results)
 }
 
-fn part2(input: &str) -> u64 {
-    let lines: Vec<Vec<char>> = {
-        let lines: Vec<&str> = input.lines().collect();
-        lines[..lines.len() - 1]
-            .iter()
-            .map(|s| s.chars().collect())
-            .collect()
+fn part2(worksheet: &Worksheet, trace: &mut dyn Trace) -> Result<Count, Overflow> {
+    let results = worksheet
+        .columns
+        .iter()
+        .zip(&worksheet.operators)
+        .map(|(column, op)| {
+            let column: Vec<Count> = column.iter().map(|n| Count::from(*n)).collect();
+            apply(op, &column, "part2 column", trace)
+        })
+        .collect::<Result<Vec<Count>, Overflow>>()?;
+    Count::sum(&results)
+}
+
+fn apply(
+    operator: &Op,
+    numbers: &[Count],
+    label: &str,
+    trace: &mut dyn Trace,
+) -> Result<Count, Overflow> {
+    let result = match operator {
+        Op::Add => Count::sum(numbers),
+        Op::Mul => Count::product(numbers),
+    }?;
+
+    let terms: Vec<String> = numbers.iter().map(Count::to_string).collect();
+    trace.step(
+        label,
+        format_args!("{} = {result}", terms.join(&format!(" {operator} "))),
+    );
+    Ok(result)
+}
+
+fn parse(input: &str) -> Result<Worksheet, ParseError> {
+    let lines: Vec<Token> = parsing::lines(input).collect();
+
+    let Some((operators, rows)) = lines.split_last().filter(|(_, rows)| !rows.is_empty()) else {
+        return Err(ParseError::end_of_input(
+            input,
+            "expected rows of numbers followed by a row of operators",
+        ));
     };
 
-    let mut operands = input
-        .lines()
-        .next_back()
-        .unwrap()
+    let operators: Vec<Op> = operators
         .split_whitespace()
-        .map(to_operation);
+        .map(to_operation)
+        .collect::<Result<_, _>>()?;
 
-    let mut total = 0;
-    let mut nums: Vec<u64> = Vec::new();
+    let numbers: Vec<Vec<u64>> = rows
+        .iter()
+        .map(|row| {
+            let numbers: Vec<u64> = row
+                .split_whitespace()
+                .map(Token::parse)
+                .collect::<Result<_, _>>()?;
+            if numbers.len() != operators.len() {
+                return Err(row.error(format!("expected {} numbers", operators.len())));
+            }
+            Ok(numbers)
+        })
+        .collect::<Result<_, _>>()?;
 
-    for col in 0..lines[0].len() {
-        let n: String = lines.iter().map(|l| l[col]).collect();
+    let columns = columns(rows)?;
+    if columns.len() != operators.len() {
+        return Err(ParseError::end_of_input(
+            input,
+            format!(
+                "expected {} problems separated by blank columns, found {}",
+                operators.len(),
+                columns.len()
+            ),
+        ));
+    }
 
-        if n.trim() == "" {
-            let op = operands.next().unwrap();
-            total += nums.clone().into_iter().reduce(get_func(&op)).unwrap();
-            nums.clear();
-        } else {
-            nums.push(n.trim().parse().unwrap());
+    Ok(Worksheet {
+        numbers,
+        columns,
+        operators,
+    })
+}
+
+/// Reads a number down each character column, starting a new problem at
+/// every blank column.
+fn columns(rows: &[Token]) -> Result<Vec<Vec<u64>>, ParseError> {
+    let lines: Vec<Vec<char>> = rows.iter().map(|r| r.text().chars().collect()).collect();
+
+    // Rows may have lost their trailing spaces, so treat missing cells as blank.
+    let width = lines.iter().map(Vec::len).max().unwrap_or(0);
+
+    let mut problems = vec![vec![]];
+    for col in 0..width {
+        let digits: String = lines
+            .iter()
+            .map(|l| l.get(col).copied().unwrap_or(' '))
+            .collect();
+
+        match digits.trim() {
+            "" => problems.push(vec![]),
+            n => {
+                let number = n.parse().map_err(|e| {
+                    ParseError::new(rows[0].line(), col + 1, format!("cannot parse `{n}`: {e}"))
+                })?;
+                problems.last_mut().unwrap().push(number);
+            }
         }
     }
 
-    let op = operands.next().unwrap();
-    total + nums.clone().into_iter().reduce(get_func(&op)).unwrap()
+    problems.retain(|problem| !problem.is_empty());
+    Ok(problems)
 }
 
-fn get_func(operator: &Op) -> impl Fn(u64, u64) -> u64 {
-    match operator {
-        Op::Add => |x, y| x + y,
-        Op::Mul => |x, y| x * y,
+fn to_operation(input: Token) -> Result<Op, ParseError> {
+    match input.text() {
+        "+" => Ok(Op::Add),
+        "*" => Ok(Op::Mul),
+        op => Err(input.error(format!("unknown operation `{op}`"))),
     }
 }
 
-fn parse(input: &str) -> (Vec<Vec<u64>>, Vec<Op>) {
-    let mut reversed = input.lines().rev();
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
 
-    let operations: Vec<Op> = reversed
-        .next()
-        .unwrap()
-        .split_whitespace()
-        .map(to_operation)
-        .collect();
+    pub struct Parsed(Worksheet);
 
-    let numbers: Vec<Vec<u64>> = reversed
-        .map(|line| {
-            line.split_whitespace()
-                .map(|n| n.parse::<u64>().unwrap())
-                .collect::<Vec<u64>>()
-        })
-        .collect();
+    /// `size` problems of four numbers, each problem one to four digits wide.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(6);
+        let mut rows = vec![String::new(); 5];
+        for problem in 0..size {
+            let width = rng.range(1, 5) as u32;
+            if problem > 0 {
+                rows.iter_mut().for_each(|row| row.push(' '));
+            }
+            for row in &mut rows[..4] {
+                row.push_str(
+                    &rng.range(10u64.pow(width - 1), 10u64.pow(width))
+                        .to_string(),
+                );
+            }
+            let op = if rng.chance(50) { "+" } else { "*" };
+            rows[4].push_str(&format!("{op:<0$}", width as usize));
+        }
+        rows.join("\n")
+    }
 
-    (numbers, operations)
-}
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
 
-fn to_operation(input: &str) -> Op {
-    match input {
-        "+" => Op::Add,
-        "*" => Op::Mul,
-        _ => panic!("Unknown operation: {input}"),
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part1(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part2(&parsed.0, &mut NoTrace)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Solver, assert_line_ending_variants};
     use crate::solutions::day6::Day6Solver;
+    use crate::trace::NoTrace;
 
-    #[test]
-    fn test_input() {
-        let input = r#"123 328  51 64 
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r#"123 328  51 64 
  45 64  387 23 
   6 98  215 314
 *   +   *   +  "#;
 
-        let solution = Day6Solver.solve(input);
+    #[test]
+    fn test_input() {
+        let solution = Day6Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "4277556");
+        assert_eq!(solution.part2, "3263827");
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day6Solver, INPUT);
+    }
+
+    /// Three-digit numbers, so every character column of a problem has a digit.
+    fn worksheets() -> impl Strategy<Value = (Vec<Vec<u64>>, Vec<bool>)> {
+        (1..5usize, 1..8usize).prop_flat_map(|(rows, cols)| {
+            (
+                prop::collection::vec(prop::collection::vec(100..1000u64, cols), rows),
+                prop::collection::vec(any::<bool>(), cols),
+            )
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_print_round_trip((numbers, adds) in worksheets()) {
+            let mut input: Vec<String> = numbers
+                .iter()
+                .map(|row| row.iter().map(u64::to_string).collect::<Vec<_>>().join(" "))
+                .collect();
+            let operators: Vec<&str> = adds.iter().map(|add| if *add { "+" } else { "*" }).collect();
+            input.push(operators.iter().map(|op| format!("{op:<3}")).collect::<Vec<_>>().join(" "));
+            let input = input.join("\n");
+
+            let worksheet = parse(&input).unwrap();
+            prop_assert_eq!(&worksheet.numbers, &numbers);
+            let printed: Vec<String> = worksheet.operators.iter().map(Op::to_string).collect();
+            prop_assert_eq!(printed, operators);
+
+            let expected: u64 = (0..adds.len())
+                .map(|col| {
+                    let column = numbers.iter().map(|row| row[col]);
+                    if adds[col] { column.sum::<u64>() } else { column.product() }
+                })
+                .sum();
+            prop_assert_eq!(part1(&worksheet, &mut NoTrace).unwrap(), Count::from(expected));
+        }
     }
 }
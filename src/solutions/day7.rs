@@ -1,21 +1,98 @@
 use std::{
-    collections::{HashMap, HashSet, VecDeque},
+    collections::{BTreeMap, HashMap, HashSet, VecDeque},
     fmt::Error,
 };
 
+use crate::answer::{Count, Overflow};
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::Trace;
+use crate::viz::{Frame, Pixmap};
 
 pub struct Day7Solver;
 
+/// Counts part 2's timelines a row at a time instead of filling a map.
+pub struct Day7RowSolver;
+
 impl solution::Solver for Day7Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let grid = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&grid, trace).to_string(),
+            part2: part2(&grid)?.to_string(),
+        })
+    }
+
+    fn visualize(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+    ) -> Result<Vec<Frame>, solution::Error> {
+        let grid = parse(input)?;
+        let (rows, cols) = *grid.keys().max().unwrap();
+        let (seen, _) = propagate(&grid);
+        let timelines = timelines(&grid)?;
+
+        let mut beams = String::new();
+        for row in 0..=rows {
+            for col in 0..=cols {
+                beams.push(match grid.get(&(row, col)) {
+                    Some(Point::Beam) => 'S',
+                    Some(Point::Splitter) => '^',
+                    _ if seen.contains(&(row, col)) => '|',
+                    _ => '.',
+                });
+            }
+            beams.push('\n');
+        }
+
+        // Timeline counts grow exponentially, so shade by their number of digits.
+        let digits = |coord: &Coord| timelines.get(coord).map_or(0, |c| c.to_string().len());
+        let max_digits = grid.keys().map(digits).max().unwrap_or(1).max(1);
+
+        let mut heatmap = Pixmap::new((cols + 1) * SCALE, (rows + 1) * SCALE, [0, 0, 0]);
+        for (coord, point) in &grid {
+            let color = match point {
+                Point::Splitter => [255, 255, 255],
+                _ => [0, (255 * digits(coord) / max_digits) as u8, 0],
+            };
+            for dy in 0..SCALE {
+                for dx in 0..SCALE {
+                    heatmap.set(coord.1 * SCALE + dx, coord.0 * SCALE + dy, color);
+                }
+            }
         }
+
+        Ok(vec![
+            Frame::text("beams", beams),
+            Frame::ppm("timelines", heatmap),
+        ])
     }
 }
 
+impl solution::Solver for Day7RowSolver {
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let grid = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&grid, trace).to_string(),
+            part2: part2_rows(&grid)?.to_string(),
+        })
+    }
+}
+
+/// Pixels per grid cell in the timeline heatmap.
+const SCALE: usize = 4;
+
 enum Point {
     Space,
     Splitter,
@@ -25,44 +102,74 @@ enum Point {
 type Coord = (usize, usize);
 type Grid = HashMap<Coord, Point>;
 
-fn part2(input: &str) -> u64 {
-    let grid = parse(input);
-    let mut results = HashMap::new();
+fn part2(grid: &Grid) -> Result<Count, Overflow> {
+    let beam_point = find_beam(grid).expect("parse checks for a beam");
+    Ok(timelines(grid)?.remove(&beam_point).unwrap())
+}
+
+/// [`part2`] keeping only the timeline counts of the row below.
+fn part2_rows(grid: &Grid) -> Result<Count, Overflow> {
+    let (beam_row, beam_col) = find_beam(grid).expect("parse checks for a beam");
+    let (rows, cols) = *grid.keys().max().unwrap();
+
+    let mut below = vec![Count::one(); cols + 1];
+    for row in (beam_row..rows).rev() {
+        below = (0..=cols)
+            .map(|col| match grid[&(row, col)] {
+                Point::Splitter => below[col - 1].checked_add(&below[col + 1]),
+                Point::Space | Point::Beam => Ok(below[col].clone()),
+            })
+            .collect::<Result<_, _>>()?;
+    }
+    Ok(below.swap_remove(beam_col))
+}
+
+/// The number of timelines a beam entering each cell ends up in.
+fn timelines(grid: &Grid) -> Result<HashMap<Coord, Count>, Overflow> {
+    let mut results: HashMap<Coord, Count> = HashMap::new();
 
     let (rows, cols) = grid.keys().max().unwrap();
 
     for row in (0..=*rows).rev() {
         for col in (0..=*cols).rev() {
             if row == *rows {
-                results.insert((row, col), 1);
+                results.insert((row, col), Count::one());
             } else {
                 match grid.get(&(row, col)) {
                     None => (),
                     Some(Point::Space) | Some(Point::Beam) => {
                         let prev = results.get(&(row + 1, col)).unwrap();
-                        results.insert((row, col), *prev);
+                        results.insert((row, col), prev.clone());
                     }
                     Some(Point::Splitter) => {
                         let p1 = results.get(&(row + 1, col - 1)).unwrap();
                         let p2 = results.get(&(row + 1, col + 1)).unwrap();
-                        results.insert((row, col), p1 + p2);
+                        results.insert((row, col), p1.checked_add(p2)?);
                     }
                 }
             }
         }
     }
 
-    let beam_point = find_beam(&grid);
-    *results.get(&beam_point).unwrap()
+    Ok(results)
 }
 
-fn part1(input: &str) -> u64 {
-    let grid = parse(input);
+fn part1(grid: &Grid, trace: &mut dyn Trace) -> u64 {
+    let (_, row_splits) = propagate(grid);
 
-    let initial_beam = find_beam(&grid);
+    for (row, count) in &row_splits {
+        trace.step("row", format_args!("{row}: {count} splits"));
+    }
+    row_splits.values().sum()
+}
+
+/// Follows the beam down the grid, returning every cell it passes through and
+/// the number of splits on each row.
+fn propagate(grid: &Grid) -> (HashSet<Coord>, BTreeMap<usize, u64>) {
+    let initial_beam = find_beam(grid).expect("parse checks for a beam");
     let mut seen = HashSet::new();
     let mut beams = VecDeque::from([initial_beam]);
-    let mut splits = 0;
+    let mut row_splits: BTreeMap<usize, u64> = BTreeMap::new();
 
     while !beams.is_empty() {
         let beam_point = beams.pop_front().unwrap();
@@ -78,7 +185,7 @@ fn part1(input: &str) -> u64 {
                 beams.push_back(next_point);
             }
             Some(Point::Splitter) => {
-                splits += 1;
+                *row_splits.entry(next_point.0).or_default() += 1;
                 let (left, right) = split_point(&next_point);
                 beams.push_back(left);
                 beams.push_back(right);
@@ -86,32 +193,61 @@ fn part1(input: &str) -> u64 {
         }
     }
 
-    splits
+    (seen, row_splits)
 }
 
 fn split_point(point: &Coord) -> (Coord, Coord) {
     ((point.0, point.1 - 1), (point.0, point.1 + 1))
 }
 
-fn find_beam(grid: &Grid) -> Coord {
-    grid.iter()
-        .find_map(|(coord, point)| match point {
-            Point::Beam => Some(*coord),
-            _ => None,
-        })
-        .unwrap()
+fn find_beam(grid: &Grid) -> Option<Coord> {
+    grid.iter().find_map(|(coord, point)| match point {
+        Point::Beam => Some(*coord),
+        _ => None,
+    })
 }
 
-fn parse(input: &str) -> Grid {
-    input
-        .lines()
+fn parse(input: &str) -> Result<Grid, ParseError> {
+    let lines: Vec<Token> = parsing::lines(input).collect();
+    let rows = parsing::grid(lines.iter().copied(), |c| Point::try_from(c).ok())?;
+    let width = rows.first().map_or(0, Vec::len);
+
+    let mut beams = 0;
+    for (line, row) in lines.iter().zip(&rows) {
+        if row.len() != width {
+            return Err(line.error(format!("expected {width} cells like the first row")));
+        }
+        let error =
+            |col: usize, message| ParseError::new(line.line(), line.column() + col, message);
+        for (col, point) in row.iter().enumerate() {
+            match point {
+                Point::Beam if beams > 0 => {
+                    return Err(error(col, "expected a single beam start `S`"));
+                }
+                Point::Beam => beams += 1,
+                Point::Splitter if col == 0 || col + 1 == width => {
+                    return Err(error(
+                        col,
+                        "a splitter on the edge sends beams off the grid",
+                    ));
+                }
+                _ => (),
+            }
+        }
+    }
+    if beams == 0 {
+        return Err(ParseError::end_of_input(input, "expected a beam start `S`"));
+    }
+
+    Ok(rows
+        .into_iter()
         .enumerate()
         .flat_map(|(row_idx, row)| {
-            row.chars()
+            row.into_iter()
                 .enumerate()
-                .map(move |(col_idx, c)| ((row_idx, col_idx), Point::try_from(c).unwrap()))
+                .map(move |(col_idx, point)| ((row_idx, col_idx), point))
         })
-        .collect()
+        .collect())
 }
 
 impl TryFrom<char> for Point {
@@ -126,14 +262,64 @@ impl TryFrom<char> for Point {
     }
 }
 
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed(Grid);
+
+    /// A `size` by `size` manifold with the beam entering at the top middle
+    /// and splitters on every other row.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(7);
+        (0..size)
+            .map(|row| {
+                (0..size)
+                    .map(|col| match (row, col) {
+                        (0, col) if col == size / 2 => 'S',
+                        (0, _) => '.',
+                        (row, col)
+                            if row % 2 == 0 && col > 0 && col + 1 < size && rng.chance(20) =>
+                        {
+                            '^'
+                        }
+                        _ => '.',
+                    })
+                    .collect::<String>()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> u64 {
+        super::part1(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<Count, Overflow> {
+        super::part2(&parsed.0)
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
-    use crate::solutions::day7::Day7Solver;
+    use super::solution::{Params, Solver, assert_line_ending_variants};
+    use crate::solutions::day7::{Day7RowSolver, Day7Solver};
 
-    #[test]
-    fn test_input() {
-        let input = r#".......S.......
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r#".......S.......
 ...............
 .......^.......
 ...............
@@ -149,8 +335,66 @@ mod tests {
 ...............
 .^.^.^.^.^...^.
 ..............."#;
-        let solution = Day7Solver.solve(input);
+
+    #[test]
+    fn test_input() {
+        let solution = Day7Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "21");
         assert_eq!(solution.part2, "40");
     }
+
+    #[test]
+    fn test_visualize() {
+        let frames = Day7Solver.visualize(INPUT, &Params::new()).unwrap();
+        let beams = String::from_utf8(frames[0].bytes()).unwrap();
+        assert_eq!(beams.lines().nth(2), Some("......|^|......"));
+        assert_eq!(frames[1].extension(), "ppm");
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day7Solver, INPUT);
+    }
+
+    #[test]
+    fn test_invalid_grids() {
+        let error = |input| Day7Solver.solve(input).unwrap_err().to_string();
+        assert_eq!(
+            error(".S.S.\n....."),
+            "invalid input at line 1, column 4: expected a single beam start `S`"
+        );
+        assert_eq!(
+            error("..S\n..^"),
+            "invalid input at line 2, column 3: a splitter on the edge sends beams off the grid"
+        );
+        assert_eq!(
+            error("..S..\n..."),
+            "invalid input at line 2, column 1: expected 5 cells like the first row"
+        );
+    }
+
+    #[test]
+    fn test_row_solver() {
+        assert_eq!(Day7RowSolver.solve(INPUT), Day7Solver.solve(INPUT));
+        assert_eq!(Day7RowSolver.solve("...\n.S.").unwrap().part2, "1");
+    }
+
+    proptest! {
+        #[test]
+        fn prop_row_dp_matches_map(
+            (width, beam, cells) in (3..12usize).prop_flat_map(|width| {
+                (Just(width), 0..width, prop::collection::vec(prop::bool::weighted(0.3), width * 8))
+            })
+        ) {
+            let mut input = (0..width).map(|col| if col == beam { 'S' } else { '.' }).collect::<String>();
+            for row in cells.chunks(width) {
+                input.push('\n');
+                input.extend(row.iter().enumerate().map(|(col, splitter)| {
+                    if *splitter && col > 0 && col + 1 < width { '^' } else { '.' }
+                }));
+            }
+            let grid = parse(&input).unwrap();
+            prop_assert_eq!(part2_rows(&grid), part2(&grid));
+        }
+    }
 }
@@ -1,33 +1,93 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, fmt};
 
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::{NoTrace, Trace};
+use crate::viz::{Frame, Svg, palette};
 
 pub struct Day8Solver;
 
 impl solution::Solver for Day8Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let points = parse(input)?;
+        let connections = params.get("connections", CONNECTIONS)?;
+        Ok(solution::Solution {
+            part1: part1(&points, connections, trace).to_string(),
+            part2: part2(&points, trace).to_string(),
+        })
+    }
+
+    fn visualize(
+        &self,
+        input: &str,
+        params: &solution::Params,
+    ) -> Result<Vec<Frame>, solution::Error> {
+        let points = parse(input)?;
+        let count = params.get("connections", CONNECTIONS)?;
+        let (mut connections, pairs) = connect_closest(&points, count, &mut NoTrace);
+
+        // Project onto the x/y plane and colour circuits by size, largest first.
+        let roots: Vec<usize> = (0..points.len()).map(|i| connections.find(i)).collect();
+        let mut sizes: HashMap<usize, usize> = HashMap::new();
+        roots
+            .iter()
+            .for_each(|r| *sizes.entry(*r).or_default() += 1);
+        let mut ranked: Vec<(usize, usize)> = sizes.into_iter().collect();
+        ranked.sort_by_key(|(root, size)| (std::cmp::Reverse(*size), *root));
+        let rank: HashMap<usize, usize> = ranked
+            .iter()
+            .enumerate()
+            .map(|(idx, (root, _))| (*root, idx))
+            .collect();
+
+        let xmax = points.iter().map(|p| p.0).max().unwrap_or(0) as f64;
+        let ymax = points.iter().map(|p| p.1).max().unwrap_or(0) as f64;
+        let radius = (xmax.max(ymax) / 200.0).max(1.0);
+        let mut svg = Svg::new(-radius, -radius, xmax + 2.0 * radius, ymax + 2.0 * radius);
+        let xy = |p: &Point| (p.0 as f64, p.1 as f64);
+
+        for (i, j) in pairs {
+            let color = palette(rank[&roots[i]]);
+            svg.line(
+                xy(&points[i]),
+                xy(&points[j]),
+                &format!("stroke:{color};stroke-width:{}", radius / 2.0),
+            );
+        }
+        for (point, root) in points.iter().zip(&roots) {
+            svg.circle(xy(point), radius, &format!("fill:{}", palette(rank[root])));
         }
+
+        Ok(vec![Frame::svg("circuits", svg)])
     }
 }
 
+/// The number of closest pairs part 1 connects in the puzzle.
+const CONNECTIONS: usize = 1000;
+
 struct Point(i64, i64, i64);
 
-type Distances = HashMap<(usize, usize), i64>;
+type Distances = HashMap<(usize, usize), i128>;
 
-fn part2(input: &str) -> u64 {
-    let points = parse(input);
+fn part2(points: &[Point], trace: &mut dyn Trace) -> u64 {
     let target = points.len() - 1;
 
-    let distances = sorted_distances(get_distances(&points));
+    let distances = sorted_distances(get_distances(points));
     let mut connections = UnionFind::new(points.len());
 
     let mut connection_count = 0;
     for (i, j) in distances.iter() {
         if connections.find(*i) != connections.find(*j) {
             connection_count += 1;
+            trace.step(
+                "part2 union",
+                format_args!("{} + {}", points[*i], points[*j]),
+            );
             if connection_count == target {
                 return (points[*i].0 * points[*j].0) as u64;
             }
@@ -37,14 +97,8 @@ fn part2(input: &str) -> u64 {
     panic!("Unreachable");
 }
 
-fn part1(input: &str) -> u64 {
-    let points = parse(input);
-    let distances = sorted_distances(get_distances(&points));
-    let mut connections = UnionFind::new(points.len());
-
-    for (i, j) in distances.iter().take(top_circuits()) {
-        connections.connect(*i, *j);
-    }
+fn part1(points: &[Point], count: usize, trace: &mut dyn Trace) -> u64 {
+    let (mut connections, _) = connect_closest(points, count, trace);
 
     let mut freqs: HashMap<usize, u64> = HashMap::new();
     for idx in 0..points.len() {
@@ -57,9 +111,32 @@ fn part1(input: &str) -> u64 {
     v.iter().take(3).product()
 }
 
+/// Connects the `count` closest pairs, returning the circuits and the pairs
+/// that were connected.
+fn connect_closest(
+    points: &[Point],
+    count: usize,
+    trace: &mut dyn Trace,
+) -> (UnionFind, Vec<(usize, usize)>) {
+    let distances = sorted_distances(get_distances(points));
+    let mut connections = UnionFind::new(points.len());
+    let pairs: Vec<(usize, usize)> = distances.into_iter().take(count).collect();
+
+    for (i, j) in pairs.iter() {
+        if connections.find(*i) != connections.find(*j) {
+            trace.step(
+                "part1 union",
+                format_args!("{} + {}", points[*i], points[*j]),
+            );
+        }
+        connections.connect(*i, *j);
+    }
+    (connections, pairs)
+}
+
 fn sorted_distances(distances: Distances) -> Vec<(usize, usize)> {
-    let mut d: Vec<((usize, usize), i64)> = distances.into_iter().collect();
-    d.sort_by(|(_points1, d1), (_points2, d2)| d1.cmp(d2));
+    let mut d: Vec<((usize, usize), i128)> = distances.into_iter().collect();
+    d.sort_by_key(|(_points, distance)| *distance);
     d.into_iter().map(|(points, _distance)| points).collect()
 }
 
@@ -74,22 +151,39 @@ fn get_distances(points: &[Point]) -> Distances {
     distances
 }
 
-fn parse(input: &str) -> Vec<Point> {
-    input.lines().map(Point::from).collect()
+fn parse(input: &str) -> Result<Vec<Point>, ParseError> {
+    let points: Vec<Point> = parsing::lines(input)
+        .map(Point::try_from)
+        .collect::<Result<_, _>>()?;
+    if points.len() < 2 {
+        return Err(ParseError::end_of_input(
+            input,
+            "expected at least two junction boxes",
+        ));
+    }
+    Ok(points)
+}
+
+impl fmt::Display for Point {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{},{}", self.0, self.1, self.2)
+    }
 }
 
 impl Point {
-    fn distance(&self, other: &Self) -> i64 {
-        (self.0 - other.0).pow(2) + (self.1 - other.1).pow(2) + (self.2 - other.2).pow(2)
+    fn distance(&self, other: &Self) -> i128 {
+        let d = |a: i64, b: i64| (a - b) as i128;
+        d(self.0, other.0).pow(2) + d(self.1, other.1).pow(2) + d(self.2, other.2).pow(2)
     }
 }
 
-impl From<&str> for Point {
-    fn from(value: &str) -> Self {
-        let nums: Vec<i64> = value.split(",").map(|n| n.parse().unwrap()).collect();
-        match nums.as_slice() {
-            [x, y, z] => Self(*x, *y, *z),
-            _ => panic!("Expected length 3 array"),
+impl TryFrom<Token<'_>> for Point {
+    type Error = ParseError;
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        // Coordinates fit in an i32 so that distances and products can't overflow.
+        match value.numbers::<i32>(",")?.as_slice() {
+            [x, y, z] => Ok(Self(*x as i64, *y as i64, *z as i64)),
+            _ => Err(value.error("expected three comma-separated coordinates")),
         }
     }
 }
@@ -120,18 +214,57 @@ impl UnionFind {
     }
 }
 
-fn top_circuits() -> usize {
-    if cfg!(test) { 10 } else { 1000 }
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+
+    pub struct Parsed(Vec<Point>);
+
+    /// `size` junction boxes in a cube a hundred thousand across.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(8);
+        (0..size)
+            .map(|_| {
+                let mut coord = || rng.range(0, 100_000);
+                format!("{},{},{}", coord(), coord(), coord())
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> u64 {
+        super::part1(&parsed.0, CONNECTIONS, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> u64 {
+        super::part2(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn get_distances(parsed: &Parsed) -> HashMap<(usize, usize), i128> {
+        super::get_distances(&parsed.0)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Params, Solver, assert_line_ending_variants};
     use crate::solutions::day8::Day8Solver;
+    use crate::trace::{NoTrace, RecordTrace};
 
-    #[test]
-    fn test_input() {
-        let input = r#"162,817,812
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r#"162,817,812
 57,618,57
 906,360,560
 592,479,940
@@ -152,8 +285,56 @@ mod tests {
 984,92,344
 425,690,689"#;
 
-        let solution = Day8Solver.solve(input);
+    #[test]
+    fn test_input() {
+        let params = Params::from([("connections", "10")]);
+        let solution = Day8Solver.solve_with(INPUT, &params, &mut NoTrace).unwrap();
         assert_eq!(solution.part1, "40");
         assert_eq!(solution.part2, "25272");
     }
+
+    #[test]
+    fn test_too_few_points() {
+        assert!(Day8Solver.solve("").is_err());
+        assert!(Day8Solver.solve("1,2,3").is_err());
+        assert!(Day8Solver.solve("1,2,3\n4,5,3000000000").is_err());
+    }
+
+    #[test]
+    fn test_invalid_param() {
+        let params = Params::from([("connections", "many")]);
+        assert!(Day8Solver.solve_with(INPUT, &params, &mut NoTrace).is_err());
+    }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day8Solver, INPUT);
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_print_round_trip(points in prop::collection::vec((any::<i32>(), any::<i32>(), any::<i32>()), 2..20)) {
+            let input: Vec<String> = points.iter().map(|(x, y, z)| format!("{x},{y},{z}")).collect();
+            let parsed = parse(&input.join("\n")).unwrap();
+            prop_assert_eq!(parsed.iter().map(Point::to_string).collect::<Vec<_>>(), input);
+        }
+
+        #[test]
+        fn prop_circuit_sizes_sum_to_points(
+            coords in prop::collection::vec((0..1000i64, 0..1000i64, 0..1000i64), 2..40),
+            count in 0..100usize,
+        ) {
+            let points: Vec<Point> = coords.iter().map(|(x, y, z)| Point(*x, *y, *z)).collect();
+            let mut trace = RecordTrace::default();
+            let (mut connections, _) = connect_closest(&points, count, &mut trace);
+            let mut sizes: HashMap<usize, usize> = HashMap::new();
+            for idx in 0..points.len() {
+                *sizes.entry(connections.find(idx)).or_default() += 1;
+            }
+            prop_assert_eq!(sizes.values().sum::<usize>(), points.len());
+            // Every union that joined two circuits leaves one fewer.
+            let unions = trace.details("part1 union").len();
+            prop_assert_eq!(sizes.len(), points.len() - unions);
+        }
+    }
 }
@@ -1,60 +1,146 @@
+use crate::parsing::{self, ParseError, Token};
 use crate::solutions::solution;
+use crate::trace::Trace;
+use crate::viz::{Frame, Svg};
+use std::fmt;
 
 pub struct Day9Solver;
 
 impl solution::Solver for Day9Solver {
-    fn solve(&self, input: &str) -> solution::Solution {
-        solution::Solution {
-            part1: part1(input).to_string(),
-            part2: part2(input).to_string(),
+    fn solve_with(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+        trace: &mut dyn Trace,
+    ) -> Result<solution::Solution, solution::Error> {
+        let coords = parse(input)?;
+        Ok(solution::Solution {
+            part1: part1(&coords, trace).to_string(),
+            part2: part2(&coords, trace)?.to_string(),
+        })
+    }
+
+    fn visualize(
+        &self,
+        input: &str,
+        _params: &solution::Params,
+    ) -> Result<Vec<Frame>, solution::Error> {
+        let coords = parse(input)?;
+
+        let (xmin, xmax) = (
+            coords.iter().map(|c| c.0).min(),
+            coords.iter().map(|c| c.0).max(),
+        );
+        let (ymin, ymax) = (
+            coords.iter().map(|c| c.1).min(),
+            coords.iter().map(|c| c.1).max(),
+        );
+        let (Some(xmin), Some(xmax), Some(ymin), Some(ymax)) = (xmin, xmax, ymin, ymax) else {
+            return Ok(vec![]);
+        };
+
+        let margin = ((xmax - xmin).max(ymax - ymin) / 50).max(1) as f64;
+        let mut svg = Svg::new(
+            xmin as f64 - margin,
+            ymin as f64 - margin,
+            (xmax - xmin) as f64 + 2.0 * margin,
+            (ymax - ymin) as f64 + 2.0 * margin,
+        );
+        let stroke = margin / 4.0;
+
+        let outline: Vec<(f64, f64)> = coords.iter().map(|c| (c.0 as f64, c.1 as f64)).collect();
+        svg.polygon(&outline, "fill:#2e7d32;stroke:#c62828");
+
+        for ((p1, p2), color) in [
+            (Some(largest(&coords)), "#1565c0"),
+            (largest_inside(&coords), "#fdd835"),
+        ]
+        .into_iter()
+        .filter_map(|(rectangle, color)| Some((rectangle?, color)))
+        {
+            let (xmin, xmax, ymin, ymax) = edges(&p1, &p2);
+            svg.rect(
+                xmin as f64,
+                ymin as f64,
+                (xmax - xmin) as f64,
+                (ymax - ymin) as f64,
+                &format!("fill:{color};fill-opacity:0.5;stroke:{color};stroke-width:{stroke}"),
+            );
         }
+
+        Ok(vec![Frame::svg("rectangles", svg)])
     }
 }
 
-fn part2(input: &str) -> u64 {
-    let coords: Vec<Coord> = input.lines().map(Coord::from).collect();
+fn part2(coords: &[Coord], trace: &mut dyn Trace) -> Result<u128, solution::Error> {
+    let Some((p1, p2)) = largest_inside(coords) else {
+        return Err(solution::Error::NoSolution(
+            "no rectangle fits inside the loop".to_string(),
+        ));
+    };
+
+    trace.step(
+        "part2 rectangle",
+        format_args!("{p1} to {p2}, area {}", p1.area(&p2)),
+    );
+    Ok(p1.area(&p2))
+}
+
+/// The largest rectangle with red corners lying entirely inside the loop. A
+/// loop that crosses itself may leave no such rectangle.
+fn largest_inside(coords: &[Coord]) -> Option<(Coord, Coord)> {
     let length = coords.len();
-    let rectangles = rectangles(&coords);
+    let rectangles = rectangles(coords);
 
-    let (p1, p2) = rectangles
-        .iter()
-        .find(|(p1, p2)| {
-            let (xmin, xmax, ymin, ymax) = edges(p1, p2);
-            for (i, c1) in coords.iter().enumerate() {
-                let c2 = &coords[(i + 1) % length];
-
-                if c1.0 == c2.0 {
-                    let (ylmin, ylmax) = (c1.1.min(c2.1), c1.1.max(c2.1));
-                    if xmin < c1.0 && xmax > c1.0 && !(ymin >= ylmax || ymax <= ylmin) {
-                        return false;
-                    }
-                } else if c1.1 == c2.1 {
-                    let (xlmin, xlmax) = (c1.0.min(c2.0), c1.0.max(c2.0));
-                    if ymin < c1.1 && ymax > c1.1 && !(xmin >= xlmax || xmax <= xlmin) {
-                        return false;
-                    }
-                } else {
-                    panic!("Unreachable");
+    rectangles.into_iter().find(|(p1, p2)| {
+        let (xmin, xmax, ymin, ymax) = edges(p1, p2);
+        for (i, c1) in coords.iter().enumerate() {
+            let c2 = &coords[(i + 1) % length];
+
+            if c1.0 == c2.0 {
+                let (ylmin, ylmax) = (c1.1.min(c2.1), c1.1.max(c2.1));
+                if xmin < c1.0 && xmax > c1.0 && !(ymin >= ylmax || ymax <= ylmin) {
+                    return false;
+                }
+            } else if c1.1 == c2.1 {
+                let (xlmin, xlmax) = (c1.0.min(c2.0), c1.0.max(c2.0));
+                if ymin < c1.1 && ymax > c1.1 && !(xmin >= xlmax || xmax <= xlmin) {
+                    return false;
                 }
+            } else {
+                panic!("Unreachable");
             }
-            true
-        })
-        .unwrap();
-
-    p1.area(p2)
+        }
+        true
+    })
 }
 
-fn part1(input: &str) -> u64 {
-    let coords: Vec<Coord> = input.lines().map(Coord::from).collect();
+fn part1(coords: &[Coord], trace: &mut dyn Trace) -> u128 {
+    let (p1, p2) = largest(coords);
+
+    trace.step(
+        "part1 rectangle",
+        format_args!("{p1} to {p2}, area {}", p1.area(&p2)),
+    );
+    p1.area(&p2)
+}
 
-    let mut max_val = u64::MIN;
+/// The largest rectangle with red corners anywhere.
+fn largest(coords: &[Coord]) -> (Coord, Coord) {
+    let mut max_val = 0;
+    let mut best = (0, 0);
 
     for i in 0..(coords.len() - 1) {
         for j in i + 1..coords.len() {
-            max_val = max_val.max(coords[i].area(&coords[j]));
+            let area = coords[i].area(&coords[j]);
+            if area > max_val {
+                max_val = area;
+                best = (i, j);
+            }
         }
     }
-    max_val
+
+    (coords[best.0].clone(), coords[best.1].clone())
 }
 
 fn edges(point1: &Coord, point2: &Coord) -> (u64, u64, u64, u64) {
@@ -74,38 +160,120 @@ fn rectangles(coords: &[Coord]) -> Vec<(Coord, Coord)> {
         }
     }
 
-    pairs.sort_by(|p1, p2| p1.0.area(&p1.1).cmp(&p2.0.area(&p2.1)));
+    pairs.sort_by_key(|(p1, p2)| p1.area(p2));
     pairs.reverse();
     pairs
 }
 
+fn parse(input: &str) -> Result<Vec<Coord>, ParseError> {
+    let lines: Vec<Token> = parsing::lines(input).collect();
+    let coords: Vec<Coord> = lines
+        .iter()
+        .map(|line| Coord::try_from(*line))
+        .collect::<Result<_, _>>()?;
+
+    if coords.len() < 2 {
+        return Err(ParseError::end_of_input(
+            input,
+            "expected at least two red tiles",
+        ));
+    }
+    for (idx, line) in lines.iter().enumerate() {
+        let (prev, tile) = (
+            &coords[(idx + coords.len() - 1) % coords.len()],
+            &coords[idx],
+        );
+        if prev.0 != tile.0 && prev.1 != tile.1 {
+            return Err(line.error(format!("{tile} shares no row or column with {prev}")));
+        }
+    }
+    Ok(coords)
+}
+
 #[derive(Clone)]
 struct Coord(u64, u64);
 
-impl From<&str> for Coord {
-    fn from(value: &str) -> Self {
-        let nums: Vec<u64> = value.split(",").map(|n| n.parse().unwrap()).collect();
-        match nums.as_slice() {
-            [x, y] => Self(*x, *y),
-            _ => panic!("Expected 2-element vector"),
+impl TryFrom<Token<'_>> for Coord {
+    type Error = ParseError;
+    fn try_from(value: Token) -> Result<Self, Self::Error> {
+        match value.numbers(",")?.as_slice() {
+            [x, y] => Ok(Self(*x, *y)),
+            _ => Err(value.error("expected two comma-separated coordinates")),
         }
     }
 }
 
+impl fmt::Display for Coord {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{},{}", self.0, self.1)
+    }
+}
+
 impl Coord {
-    fn area(&self, other: &Self) -> u64 {
-        (self.0.abs_diff(other.0) + 1) * (self.1.abs_diff(other.1) + 1)
+    fn area(&self, other: &Self) -> u128 {
+        (self.0.abs_diff(other.0) as u128 + 1) * (self.1.abs_diff(other.1) as u128 + 1)
+    }
+}
+
+#[cfg(feature = "bench")]
+pub mod bench {
+    use super::*;
+    use crate::bench::Rng;
+    use crate::trace::NoTrace;
+
+    pub struct Parsed(Vec<Coord>);
+
+    /// A loop of `size` steps up and down along the floor, like a histogram.
+    pub fn input(size: usize) -> String {
+        let mut rng = Rng::new(9);
+        let mut x = rng.range(0, 1000);
+        let mut y = 0;
+        let mut tiles = vec![Coord(x, y)];
+        for _ in 0..size {
+            y = match rng.range(1, 100_000) {
+                same if same == y => same + 1,
+                next => next,
+            };
+            tiles.push(Coord(x, y));
+            x += rng.range(1, 1000);
+            tiles.push(Coord(x, y));
+        }
+        tiles.push(Coord(x, 0));
+        tiles
+            .iter()
+            .map(Coord::to_string)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// The parse alone, for fuzzing.
+    pub fn try_parse(input: &str) -> Result<Parsed, ParseError> {
+        Ok(Parsed(super::parse(input)?))
+    }
+
+    pub fn parse(input: &str) -> Parsed {
+        try_parse(input).unwrap()
+    }
+
+    pub fn part1(parsed: &Parsed) -> u128 {
+        super::part1(&parsed.0, &mut NoTrace)
+    }
+
+    pub fn part2(parsed: &Parsed) -> Result<u128, solution::Error> {
+        super::part2(&parsed.0, &mut NoTrace)
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use super::solution::Solver;
+    use super::solution::{Solver, assert_line_ending_variants};
     use crate::solutions::day9::Day9Solver;
+    use crate::trace::NoTrace;
 
-    #[test]
-    fn test_input() {
-        let input = r#"7,1
+    use super::*;
+    use proptest::prelude::*;
+
+    const INPUT: &str = r#"7,1
 11,1
 11,7
 9,7
@@ -113,8 +281,46 @@ mod tests {
 2,5
 2,3
 7,3"#;
-        let solution = Day9Solver.solve(input);
+
+    #[test]
+    fn test_input() {
+        let solution = Day9Solver.solve(INPUT).unwrap();
         assert_eq!(solution.part1, "50");
         assert_eq!(solution.part2, "24");
     }
+
+    #[test]
+    fn test_line_endings() {
+        assert_line_ending_variants(&Day9Solver, INPUT);
+    }
+
+    #[test]
+    fn test_invalid_loops() {
+        assert!(Day9Solver.solve("").is_err());
+        assert!(Day9Solver.solve("1,1").is_err());
+        assert_eq!(
+            Day9Solver.solve("1,1\n3,3").unwrap_err().to_string(),
+            "invalid input at line 1, column 1: 1,1 shares no row or column with 3,3"
+        );
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_print_round_trip(coords in prop::collection::vec((any::<u64>(), any::<u64>()), 1..20)) {
+            let input: Vec<String> = coords.iter().map(|(x, y)| format!("{x},{y}")).collect();
+            let parsed: Vec<Coord> = parsing::lines(&input.join("\n"))
+                .map(Coord::try_from)
+                .collect::<Result<_, _>>()
+                .unwrap();
+            prop_assert_eq!(parsed.iter().map(Coord::to_string).collect::<Vec<_>>(), input);
+        }
+
+        #[test]
+        fn prop_rectangle_loop_fills_itself(x in 0..1000u64, y in 0..1000u64, w in 1..100u64, h in 1..100u64) {
+            let coords = [Coord(x, y), Coord(x + w, y), Coord(x + w, y + h), Coord(x, y + h)];
+            let area = ((w + 1) * (h + 1)) as u128;
+            prop_assert_eq!(part1(&coords, &mut NoTrace), area);
+            prop_assert_eq!(part2(&coords, &mut NoTrace).unwrap(), area);
+        }
+    }
 }
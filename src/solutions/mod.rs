@@ -11,4 +11,35 @@ pub mod day6;
 pub mod day7;
 pub mod day8;
 pub mod day9;
+pub mod registry;
 pub mod solution;
+
+/// Other ways to solve some days, as `(day, name, solver)`, kept to compare
+/// against the built-in solvers.
+pub fn alternatives() -> Vec<(u8, &'static str, Box<dyn solution::Solver>)> {
+    vec![
+        (2, "listing", Box::new(day2::Day2ListingSolver)),
+        (7, "row-dp", Box::new(day7::Day7RowSolver)),
+        (10, "elimination", Box::new(day10::Day10EliminationSolver)),
+    ]
+}
+
+/// The solver for `day`, if there is one.
+pub fn solver(day: u8) -> Option<Box<dyn solution::Solver>> {
+    Some(match day {
+        0 => Box::new(day0::Day0Solver),
+        1 => Box::new(day1::Day1Solver),
+        2 => Box::new(day2::Day2Solver),
+        3 => Box::new(day3::Day3Solver),
+        4 => Box::new(day4::Day4Solver),
+        5 => Box::new(day5::Day5Solver),
+        6 => Box::new(day6::Day6Solver),
+        7 => Box::new(day7::Day7Solver),
+        8 => Box::new(day8::Day8Solver),
+        9 => Box::new(day9::Day9Solver),
+        10 => Box::new(day10::Day10Solver),
+        11 => Box::new(day11::Day11Solver),
+        12 => Box::new(day12::Day12Solver),
+        _ => return None,
+    })
+}
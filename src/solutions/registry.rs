@@ -0,0 +1,96 @@
+use crate::solutions::{self, solution::Solver};
+
+/// Solvers by day and name: the built-in `DayNSolver`s, named
+/// [`Registry::BUILTIN`], their [`solutions::alternatives`], and any registered
+/// later, such as plugins.
+pub struct Registry {
+    solvers: Vec<(u8, String, Box<dyn Solver>)>,
+}
+
+impl Registry {
+    pub const BUILTIN: &str = "builtin";
+
+    /// A registry holding the built-in solvers and their alternatives.
+    pub fn new() -> Self {
+        let mut registry = Self { solvers: vec![] };
+        for day in 0..=u8::MAX {
+            if let Some(solver) = solutions::solver(day) {
+                registry
+                    .solvers
+                    .push((day, Self::BUILTIN.to_string(), solver));
+            }
+        }
+        for (day, name, solver) in solutions::alternatives() {
+            registry
+                .register(day, name, solver)
+                .expect("Alternative solver names are unique");
+        }
+        registry
+    }
+
+    /// Adds `solver` for `day`, unless a solver is already registered under
+    /// the same name.
+    pub fn register(
+        &mut self,
+        day: u8,
+        name: impl Into<String>,
+        solver: Box<dyn Solver>,
+    ) -> Result<(), String> {
+        let name = name.into();
+        if self.get(day, &name).is_some() {
+            return Err(format!("day {day} already has a solver named `{name}`"));
+        }
+        self.solvers.push((day, name, solver));
+        Ok(())
+    }
+
+    pub fn get(&self, day: u8, name: &str) -> Option<&dyn Solver> {
+        self.solvers
+            .iter()
+            .find(|(d, n, _)| *d == day && n == name)
+            .map(|(_, _, solver)| solver.as_ref())
+    }
+
+    /// The names of the solvers for `day`, in the order they were registered.
+    pub fn names(&self, day: u8) -> Vec<&str> {
+        self.solvers
+            .iter()
+            .filter(|(d, _, _)| *d == day)
+            .map(|(_, name, _)| name.as_str())
+            .collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::solutions::day0::Day0Solver;
+
+    #[test]
+    fn test_register() {
+        let mut registry = Registry::new();
+        assert_eq!(registry.names(1), vec![Registry::BUILTIN]);
+        assert_eq!(registry.names(7), vec![Registry::BUILTIN, "row-dp"]);
+        assert!(registry.get(13, Registry::BUILTIN).is_none());
+
+        registry.register(1, "other", Box::new(Day0Solver)).unwrap();
+        assert_eq!(
+            registry.register(1, "other", Box::new(Day0Solver)),
+            Err("day 1 already has a solver named `other`".to_string())
+        );
+        assert!(
+            registry
+                .register(2, Registry::BUILTIN, Box::new(Day0Solver))
+                .is_err()
+        );
+        assert_eq!(registry.names(1), vec![Registry::BUILTIN, "other"]);
+        assert!(registry.get(1, "other").is_some());
+        assert!(registry.get(2, "other").is_none());
+    }
+}
@@ -1,8 +1,132 @@
+use crate::answer::Overflow;
+use crate::parsing::ParseError;
+use crate::trace::{NoTrace, Trace};
+use crate::viz::Frame;
+use std::{collections::HashMap, fmt, str::FromStr};
+
+#[derive(Debug, PartialEq, Eq)]
 pub struct Solution {
     pub part1: String,
     pub part2: String,
 }
 
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    Parse(ParseError),
+    Overflow(Overflow),
+    Param(String),
+    /// The input is well-formed but the puzzle has no answer for it.
+    NoSolution(String),
+    /// A solver loaded from a plugin reported a failure.
+    Plugin(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Parse(e) => write!(f, "invalid input at {e}"),
+            Error::Overflow(e) => e.fmt(f),
+            Error::Param(message) => write!(f, "invalid parameter: {message}"),
+            Error::NoSolution(message) => write!(f, "no solution: {message}"),
+            Error::Plugin(message) => write!(f, "plugin failed: {message}"),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<ParseError> for Error {
+    fn from(value: ParseError) -> Self {
+        Error::Parse(value)
+    }
+}
+
+impl From<Overflow> for Error {
+    fn from(value: Overflow) -> Self {
+        Error::Overflow(value)
+    }
+}
+
+/// Named settings that tweak how a solver runs, such as day8's number of
+/// connections. Solvers fall back to the puzzle's values for missing keys.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Params(HashMap<String, String>);
+
+impl Params {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&mut self, key: impl Into<String>, value: impl Into<String>) {
+        self.0.insert(key.into(), value.into());
+    }
+
+    pub fn get<T: FromStr>(&self, key: &str, default: T) -> Result<T, Error> {
+        match self.0.get(key) {
+            None => Ok(default),
+            Some(value) => value
+                .parse()
+                .map_err(|_| Error::Param(format!("`{value}` is not a valid `{key}`"))),
+        }
+    }
+
+    /// Parses a `key=value` pair, as given on the command line.
+    pub fn parse_pair(pair: &str) -> Result<(String, String), Error> {
+        match pair.split_once('=') {
+            Some((key, value)) => Ok((key.to_string(), value.to_string())),
+            None => Err(Error::Param(format!(
+                "expected `key=value`, found `{pair}`"
+            ))),
+        }
+    }
+}
+
+impl<const N: usize> From<[(&str, &str); N]> for Params {
+    fn from(value: [(&str, &str); N]) -> Self {
+        Self(
+            value
+                .into_iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        )
+    }
+}
+
 pub trait Solver {
-    fn solve(&self, input: &str) -> Solution;
+    /// Solves both parts with the given `params`, reporting intermediate
+    /// steps to `trace`.
+    fn solve_with(
+        &self,
+        input: &str,
+        params: &Params,
+        trace: &mut dyn Trace,
+    ) -> Result<Solution, Error>;
+
+    fn solve(&self, input: &str) -> Result<Solution, Error> {
+        self.solve_with(input, &Params::new(), &mut NoTrace)
+    }
+
+    /// Pictures of what the solver does with `input`. Days without a
+    /// visualisation return no frames.
+    fn visualize(&self, _input: &str, _params: &Params) -> Result<Vec<Frame>, Error> {
+        Ok(vec![])
+    }
+}
+
+/// Checks that `solver` gives the same answers for LF, CRLF, trailing-newline
+/// and normalised variants of `input`.
+#[cfg(test)]
+pub fn assert_line_ending_variants(solver: &dyn Solver, input: &str) {
+    let expected = solver.solve(input).unwrap();
+    let crlf = input.replace('\n', "\r\n");
+
+    for variant in [
+        format!("{input}\n"),
+        format!("{input}\n\n"),
+        crlf.clone(),
+        format!("{crlf}\r\n"),
+        crate::parsing::normalize(&crlf),
+    ] {
+        assert_eq!(solver.solve(&variant).unwrap(), expected, "{variant:?}");
+    }
 }
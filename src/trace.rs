@@ -0,0 +1,73 @@
+use std::fmt;
+
+/// Receives the intermediate steps a solver reports while it runs.
+///
+/// `label` names the kind of step (e.g. `"rotation"`) and `detail` carries
+/// its values; formatting is deferred so disabled traces cost nothing.
+pub trait Trace {
+    fn step(&mut self, label: &str, detail: fmt::Arguments);
+
+    /// Whether steps are kept at all, so solvers can skip work done only to
+    /// report them.
+    fn enabled(&self) -> bool {
+        true
+    }
+}
+
+/// Discards every step.
+pub struct NoTrace;
+
+impl Trace for NoTrace {
+    fn step(&mut self, _label: &str, _detail: fmt::Arguments) {}
+
+    fn enabled(&self) -> bool {
+        false
+    }
+}
+
+/// Prints every step to stdout, as used by `--explain`.
+pub struct PrintTrace;
+
+impl Trace for PrintTrace {
+    fn step(&mut self, label: &str, detail: fmt::Arguments) {
+        println!("[{label}] {detail}");
+    }
+}
+
+/// Keeps every step in memory.
+#[derive(Default)]
+pub struct RecordTrace {
+    pub steps: Vec<(String, String)>,
+}
+
+impl RecordTrace {
+    /// The details of every step with the given label, in order.
+    pub fn details(&self, label: &str) -> Vec<&str> {
+        self.steps
+            .iter()
+            .filter(|(l, _)| l == label)
+            .map(|(_, detail)| detail.as_str())
+            .collect()
+    }
+}
+
+impl Trace for RecordTrace {
+    fn step(&mut self, label: &str, detail: fmt::Arguments) {
+        self.steps.push((label.to_string(), detail.to_string()));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record() {
+        let mut trace = RecordTrace::default();
+        trace.step("a", format_args!("{}", 1));
+        trace.step("b", format_args!("two"));
+        trace.step("a", format_args!("{}-{}", 3, 4));
+        assert_eq!(trace.details("a"), vec!["1", "3-4"]);
+        assert_eq!(trace.steps.len(), 3);
+    }
+}
@@ -0,0 +1,201 @@
+use std::fmt::Write as _;
+use std::{fs, io, path::Path};
+
+/// One picture of a solver's state, written to its own file.
+pub struct Frame {
+    pub name: String,
+    pub content: Content,
+}
+
+pub enum Content {
+    Text(String),
+    Svg(String),
+    Ppm(Pixmap),
+}
+
+impl Frame {
+    pub fn text(name: impl Into<String>, text: String) -> Self {
+        Self {
+            name: name.into(),
+            content: Content::Text(text),
+        }
+    }
+
+    pub fn svg(name: impl Into<String>, svg: Svg) -> Self {
+        Self {
+            name: name.into(),
+            content: Content::Svg(svg.finish()),
+        }
+    }
+
+    pub fn ppm(name: impl Into<String>, pixmap: Pixmap) -> Self {
+        Self {
+            name: name.into(),
+            content: Content::Ppm(pixmap),
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self.content {
+            Content::Text(_) => "txt",
+            Content::Svg(_) => "svg",
+            Content::Ppm(_) => "ppm",
+        }
+    }
+
+    pub fn bytes(&self) -> Vec<u8> {
+        match &self.content {
+            Content::Text(text) | Content::Svg(text) => text.as_bytes().to_vec(),
+            Content::Ppm(pixmap) => pixmap.to_ppm(),
+        }
+    }
+}
+
+/// Writes `frames` to `dir` as `<prefix>-<index>-<name>.<ext>`, creating the
+/// directory if needed.
+pub fn write_frames(dir: &Path, prefix: &str, frames: &[Frame]) -> io::Result<()> {
+    fs::create_dir_all(dir)?;
+    for (idx, frame) in frames.iter().enumerate() {
+        let file = format!("{prefix}-{idx:04}-{}.{}", frame.name, frame.extension());
+        fs::write(dir.join(file), frame.bytes())?;
+    }
+    Ok(())
+}
+
+pub type Rgb = [u8; 3];
+
+/// An RGB image, saved as binary PPM.
+pub struct Pixmap {
+    width: usize,
+    height: usize,
+    pixels: Vec<Rgb>,
+}
+
+impl Pixmap {
+    pub fn new(width: usize, height: usize, background: Rgb) -> Self {
+        Self {
+            width,
+            height,
+            pixels: vec![background; width * height],
+        }
+    }
+
+    pub fn set(&mut self, x: usize, y: usize, color: Rgb) {
+        if x < self.width && y < self.height {
+            self.pixels[y * self.width + x] = color;
+        }
+    }
+
+    pub fn get(&self, x: usize, y: usize) -> Rgb {
+        self.pixels[y * self.width + x]
+    }
+
+    pub fn to_ppm(&self) -> Vec<u8> {
+        let mut bytes = format!("P6\n{} {}\n255\n", self.width, self.height).into_bytes();
+        bytes.extend(self.pixels.iter().flatten());
+        bytes
+    }
+}
+
+/// A minimal SVG document builder.
+pub struct Svg {
+    body: String,
+    view_box: (f64, f64, f64, f64),
+}
+
+impl Svg {
+    /// A document showing the area from `(x, y)` spanning `width` by `height`.
+    pub fn new(x: f64, y: f64, width: f64, height: f64) -> Self {
+        Self {
+            body: String::new(),
+            view_box: (x, y, width, height),
+        }
+    }
+
+    pub fn polygon(&mut self, points: &[(f64, f64)], style: &str) {
+        let points: Vec<String> = points.iter().map(|(x, y)| format!("{x},{y}")).collect();
+        writeln!(
+            self.body,
+            r#"<polygon points="{}" style="{style}"/>"#,
+            points.join(" ")
+        )
+        .unwrap();
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, style: &str) {
+        writeln!(
+            self.body,
+            r#"<rect x="{x}" y="{y}" width="{width}" height="{height}" style="{style}"/>"#
+        )
+        .unwrap();
+    }
+
+    pub fn line(&mut self, from: (f64, f64), to: (f64, f64), style: &str) {
+        writeln!(
+            self.body,
+            r#"<line x1="{}" y1="{}" x2="{}" y2="{}" style="{style}"/>"#,
+            from.0, from.1, to.0, to.1
+        )
+        .unwrap();
+    }
+
+    pub fn circle(&mut self, center: (f64, f64), radius: f64, style: &str) {
+        writeln!(
+            self.body,
+            r#"<circle cx="{}" cy="{}" r="{radius}" style="{style}"/>"#,
+            center.0, center.1
+        )
+        .unwrap();
+    }
+
+    pub fn finish(self) -> String {
+        let (x, y, width, height) = self.view_box;
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"{x} {y} {width} {height}\">\n{}</svg>\n",
+            self.body
+        )
+    }
+}
+
+/// A distinct colour for each small integer, for telling groups apart.
+pub fn palette(idx: usize) -> String {
+    format!("hsl({}, 70%, 50%)", (idx * 137) % 360)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ppm() {
+        let mut pixmap = Pixmap::new(2, 1, [0, 0, 0]);
+        pixmap.set(1, 0, [255, 1, 2]);
+        pixmap.set(5, 5, [9, 9, 9]);
+        assert_eq!(
+            pixmap.to_ppm(),
+            b"P6\n2 1\n255\n\0\0\0\xff\x01\x02".to_vec()
+        );
+    }
+
+    #[test]
+    fn test_svg() {
+        let mut svg = Svg::new(0.0, 0.0, 4.0, 2.0);
+        svg.rect(1.0, 0.0, 2.0, 1.5, "fill:red");
+        assert_eq!(
+            svg.finish(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 4 2\">\n<rect x=\"1\" y=\"0\" width=\"2\" height=\"1.5\" style=\"fill:red\"/>\n</svg>\n"
+        );
+    }
+
+    #[test]
+    fn test_write_frames() {
+        let dir = std::env::temp_dir().join(format!("aoc-viz-{}", std::process::id()));
+        let frames = [Frame::text("a", "x".into()), Frame::text("b", "y".into())];
+        write_frames(&dir, "day0", &frames).unwrap();
+        assert_eq!(
+            fs::read_to_string(dir.join("day0-0001-b.txt")).unwrap(),
+            "y"
+        );
+        fs::remove_dir_all(dir).unwrap();
+    }
+}